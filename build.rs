@@ -39,5 +39,5 @@ fn main() -> Result<()> {
     // We set strict to false because when building the package there migth be language codes
     // that still do not have confidence thresholds
     // for example, in the middle of adding support for more languages
-    binarize(&save_path, &model_path, false)
+    binarize(&save_path, &model_path, false, false)
 }
@@ -0,0 +1,73 @@
+#ifndef HELIPORT_H
+#define HELIPORT_H
+
+/* Generated with cbindgen, do not edit manually */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Size of the buffer needed to store a language code, including the NUL terminator
+ */
+#define HELIPORT_LANG_SIZE 8
+
+typedef struct Identifier Identifier;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Load the binarized model from `model_path` and create an identifier.
+ *
+ * Returns NULL on failure and, if `error` is not NULL, sets it to the error message,
+ * which has to be released with [`heliport_string_free`].
+ *
+ * # Safety
+ * `model_path` must be a valid C string and `error` NULL or a valid pointer.
+ */
+Identifier *heliport_identifier_create(const char *model_path, char **error);
+
+/**
+ * Identify the language of `text`.
+ *
+ * Writes the language code to `lang_out`, that needs at least
+ * [`HELIPORT_LANG_SIZE`] bytes, and the score to `score_out` if it is not NULL.
+ * Predictions below the confidence threshold are labeled as 'und'.
+ *
+ * Returns 0 on success and -1 if any of the arguments is NULL or `text` is not valid UTF-8.
+ *
+ * # Safety
+ * `id` must be a pointer returned by [`heliport_identifier_create`], `text` a valid
+ * C string and `lang_out` a buffer of at least [`HELIPORT_LANG_SIZE`] bytes.
+ */
+int heliport_identifier_identify(Identifier *id,
+                                 const char *text,
+                                 char *lang_out,
+                                 float *score_out);
+
+/**
+ * Release an identifier. Does nothing if `id` is NULL.
+ *
+ * # Safety
+ * `id` must be NULL or a pointer returned by [`heliport_identifier_create`]
+ * that has not been released yet.
+ */
+void heliport_identifier_destroy(Identifier *id);
+
+/**
+ * Release a string allocated by heliport. Does nothing if `s` is NULL.
+ *
+ * # Safety
+ * `s` must be NULL or a string returned by heliport that has not been released yet.
+ */
+void heliport_string_free(char *s);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* HELIPORT_H */
@@ -0,0 +1,158 @@
+//! C bindings for heliport.
+//!
+//! The header `heliport.h` is generated from this file with `cbindgen`.
+//! All the strings are expected to be NUL terminated and UTF-8 encoded.
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::path::Path;
+use std::ptr;
+
+pub use heliport::identifier::Identifier;
+
+/// Size of the buffer needed to store a language code, including the NUL terminator
+pub const HELIPORT_LANG_SIZE: usize = 8;
+
+// Copy a message to a newly allocated C string in the error pointer, if not null
+unsafe fn set_error(error: *mut *mut c_char, msg: String) {
+    if error.is_null() {
+        return;
+    }
+    // Messages never contain NUL, but just in case, do not fail on them
+    let msg = CString::new(msg.replace('\0', "")).unwrap_or_default();
+    *error = msg.into_raw();
+}
+
+/// Load the binarized model from `model_path` and create an identifier.
+///
+/// Returns NULL on failure and, if `error` is not NULL, sets it to the error message,
+/// which has to be released with [`heliport_string_free`].
+///
+/// # Safety
+/// `model_path` must be a valid C string and `error` NULL or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn heliport_identifier_create(
+    model_path: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut Identifier {
+    if model_path.is_null() {
+        set_error(error, String::from("Model path is NULL"));
+        return ptr::null_mut();
+    }
+    let model_path = match CStr::from_ptr(model_path).to_str() {
+        Ok(p) => p,
+        Err(e) => {
+            set_error(error, format!("Model path is not valid UTF-8: {e}"));
+            return ptr::null_mut();
+        }
+    };
+    match Identifier::load(Path::new(model_path), None, None) {
+        Ok(identifier) => Box::into_raw(Box::new(identifier)),
+        Err(e) => {
+            set_error(error, format!("{e:#}"));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Identify the language of `text`.
+///
+/// Writes the language code to `lang_out`, that needs at least
+/// [`HELIPORT_LANG_SIZE`] bytes, and the score to `score_out` if it is not NULL.
+/// Predictions below the confidence threshold are labeled as 'und'.
+///
+/// Returns 0 on success and -1 if any of the arguments is NULL or `text` is not valid UTF-8.
+///
+/// # Safety
+/// `id` must be a pointer returned by [`heliport_identifier_create`], `text` a valid
+/// C string and `lang_out` a buffer of at least [`HELIPORT_LANG_SIZE`] bytes.
+#[no_mangle]
+pub unsafe extern "C" fn heliport_identifier_identify(
+    id: *mut Identifier,
+    text: *const c_char,
+    lang_out: *mut c_char,
+    score_out: *mut f32,
+) -> c_int {
+    if id.is_null() || text.is_null() || lang_out.is_null() {
+        return -1;
+    }
+    let Ok(text) = CStr::from_ptr(text).to_str() else {
+        return -1;
+    };
+    let (lang, score) = (*id).identify(text, false);
+
+    let code = lang.to_string();
+    let len = code.len().min(HELIPORT_LANG_SIZE - 1);
+    ptr::copy_nonoverlapping(code.as_ptr() as *const c_char, lang_out, len);
+    *lang_out.add(len) = 0;
+    if !score_out.is_null() {
+        *score_out = score;
+    }
+    0
+}
+
+/// Release an identifier. Does nothing if `id` is NULL.
+///
+/// # Safety
+/// `id` must be NULL or a pointer returned by [`heliport_identifier_create`]
+/// that has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn heliport_identifier_destroy(id: *mut Identifier) {
+    if !id.is_null() {
+        drop(Box::from_raw(id));
+    }
+}
+
+/// Release a string allocated by heliport. Does nothing if `s` is NULL.
+///
+/// # Safety
+/// `s` must be NULL or a string returned by heliport that has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn heliport_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL_DIR: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../heliport.data/platlib/heliport\0"
+    );
+
+    #[test]
+    fn test_identify() {
+        unsafe {
+            let mut error = ptr::null_mut();
+            let id = heliport_identifier_create(MODEL_DIR.as_ptr() as *const c_char, &mut error);
+            assert!(!id.is_null());
+            assert!(error.is_null());
+
+            let mut lang = [0 as c_char; HELIPORT_LANG_SIZE];
+            let mut score = 0.0;
+            let text = c"Això és una frase en català";
+            let ret =
+                heliport_identifier_identify(id, text.as_ptr(), lang.as_mut_ptr(), &mut score);
+            assert_eq!(ret, 0);
+            assert_eq!(CStr::from_ptr(lang.as_ptr()).to_str().unwrap(), "cat");
+            assert!(score > 0.0);
+
+            let ret = heliport_identifier_identify(id, ptr::null(), lang.as_mut_ptr(), &mut score);
+            assert_eq!(ret, -1);
+            heliport_identifier_destroy(id);
+        }
+    }
+
+    #[test]
+    fn test_create_error() {
+        unsafe {
+            let mut error = ptr::null_mut();
+            let id = heliport_identifier_create(c"/nonexistent".as_ptr(), &mut error);
+            assert!(id.is_null());
+            assert!(!error.is_null());
+            assert!(!CStr::from_ptr(error).to_bytes().is_empty());
+            heliport_string_free(error);
+        }
+    }
+}
@@ -0,0 +1,5 @@
+//! Protocol buffers definition of the heliport gRPC service,
+//! with the generated client and server code.
+//!
+//! The server is implemented by the `heliport serve` command.
+tonic::include_proto!("heliport");
@@ -1,9 +1,14 @@
 #![allow(non_camel_case_types)]
 use std::collections::HashMap;
 use std::fmt;
-use std::ops::Index;
+use std::iter;
+use std::ops::{BitAnd, BitOr, BitXor, Index, Not};
+use std::slice;
+use std::str::FromStr;
 
-use strum::{Display, EnumCount, EnumString, FromRepr, IntoEnumIterator};
+use anyhow::{bail, Context, Result};
+
+use strum::{Display, EnumCount, EnumString, FromRepr, IntoEnumIterator, IntoStaticStr};
 use strum_macros::EnumIter;
 
 use bitcode;
@@ -22,6 +27,7 @@ use bitcode;
     EnumCount,
     EnumString,
     FromRepr,
+    IntoStaticStr,
 )]
 #[strum(serialize_all = "lowercase")]
 #[repr(u8)]
@@ -270,6 +276,27 @@ pub enum Lang {
     zxx,
 }
 
+/// Coarse classification of languages by family or, for large families, by branch
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Display, EnumIter, IntoStaticStr)]
+pub enum LangFamily {
+    Austronesian,
+    Baltic,
+    Celtic,
+    Dravidian,
+    Germanic,
+    IndoIranian,
+    Japonic,
+    Koreanic,
+    NigerCongo,
+    Romance,
+    Semitic,
+    SinoTibetan,
+    Slavic,
+    Turkic,
+    Uralic,
+    Other,
+}
+
 impl Lang {
     pub fn is_cjk(&self) -> bool {
         *self == Lang::jpn || *self == Lang::kor || *self == Lang::cmn || *self == Lang::cdo
@@ -299,6 +326,619 @@ impl Lang {
             _ => self.clone(),
         }
     }
+
+    /// English name of the language, following CLDR naming when available
+    ///
+    /// Varieties of a language that only differ in the training data share its name.
+    pub fn human_name(&self) -> &'static str {
+        match self {
+            Lang::abk => "Abkhazian",
+            Lang::ace => "Acehnese",
+            Lang::adz => "Adzera",
+            Lang::afr => "Afrikaans",
+            Lang::aii => "Assyrian Neo-Aramaic",
+            Lang::ame => "Yanesha'",
+            Lang::amh => "Amharic",
+            Lang::amr => "Amarakaeri",
+            Lang::ara => "Arabic",
+            Lang::arl => "Arabela",
+            Lang::arn => "Mapuche",
+            Lang::asm => "Assamese",
+            Lang::ayr => "Aymara",
+            Lang::aze => "Azerbaijani",
+            Lang::bak => "Bashkir",
+            Lang::bar => "Bavarian",
+            Lang::bcl => "Central Bikol",
+            Lang::bel => "Belarusian",
+            Lang::ben => "Bengali",
+            Lang::boa => "Bora",
+            Lang::bod => "Tibetan",
+            Lang::bpy => "Bishnupriya",
+            Lang::bre => "Breton",
+            Lang::bul => "Bulgarian",
+            Lang::cat => "Catalan",
+            Lang::cbu => "Candoshi-Shapra",
+            Lang::cdo => "Min Dong Chinese",
+            Lang::ceb => "Cebuano",
+            Lang::ces => "Czech",
+            Lang::che => "Chechen",
+            Lang::chk => "Chuukese",
+            Lang::cho => "Choctaw",
+            Lang::chr => "Cherokee",
+            Lang::chv => "Chuvash",
+            Lang::chy => "Cheyenne",
+            Lang::ckb => "Central Kurdish",
+            Lang::cmn => "Mandarin Chinese",
+            Lang::cnh => "Hakha Chin",
+            Lang::cor => "Cornish",
+            Lang::cos => "Corsican",
+            Lang::cym => "Welsh",
+            Lang::dan => "Danish",
+            Lang::deu => "German",
+            Lang::dik => "Southwestern Dinka",
+            Lang::diq => "Zazaki",
+            Lang::div => "Dhivehi",
+            Lang::ell => "Greek",
+            Lang::eng => "English",
+            Lang::epo => "Esperanto",
+            Lang::est => "Estonian",
+            Lang::estvro => "Võro",
+            Lang::eus => "Basque",
+            Lang::ewe => "Ewe",
+            Lang::ext => "Extremaduran",
+            Lang::fao => "Faroese",
+            Lang::fij => "Fijian",
+            Lang::fin
+            | Lang::fini
+            | Lang::fink
+            | Lang::finl
+            | Lang::finm
+            | Lang::fino
+            | Lang::finp
+            | Lang::finr
+            | Lang::fins
+            | Lang::fint
+            | Lang::finx => "Finnish",
+            Lang::fon => "Fon",
+            Lang::fra => "French",
+            Lang::fry => "Western Frisian",
+            Lang::gaz => "Oromo",
+            Lang::gla => "Scottish Gaelic",
+            Lang::gle => "Irish",
+            Lang::glg => "Galician",
+            Lang::glv => "Manx",
+            Lang::gom => "Goan Konkani",
+            Lang::grn => "Guarani",
+            Lang::gsw => "Swiss German",
+            Lang::guj => "Gujarati",
+            Lang::hat => "Haitian Creole",
+            Lang::hbs => "Serbo-Croatian",
+            Lang::hbsbos => "Bosnian",
+            Lang::hbshrv => "Croatian",
+            Lang::hbssrp => "Serbian",
+            Lang::heb => "Hebrew",
+            Lang::hin => "Hindi",
+            Lang::hmo => "Hiri Motu",
+            Lang::hsb => "Upper Sorbian",
+            Lang::hun => "Hungarian",
+            Lang::hus => "Huastec",
+            Lang::huu => "Murui Huitoto",
+            Lang::hye => "Armenian",
+            Lang::ibo => "Igbo",
+            Lang::ido => "Ido",
+            Lang::iku => "Inuktitut",
+            Lang::ilo => "Iloko",
+            Lang::ina => "Interlingua",
+            Lang::isl => "Icelandic",
+            Lang::ita => "Italian",
+            Lang::izh => "Ingrian",
+            Lang::jpn => "Japanese",
+            Lang::kac => "Kachin",
+            Lang::kal => "Kalaallisut",
+            Lang::kan => "Kannada",
+            Lang::kat => "Georgian",
+            Lang::kaz => "Kazakh",
+            Lang::kbd => "Kabardian",
+            Lang::kbp => "Kabiyè",
+            Lang::kca => "Khanty",
+            Lang::khm => "Khmer",
+            Lang::kir => "Kyrgyz",
+            Lang::kmr => "Northern Kurdish",
+            Lang::knc => "Central Kanuri",
+            Lang::koi => "Komi-Permyak",
+            Lang::kor => "Korean",
+            Lang::kpv => "Komi-Zyrian",
+            Lang::krc => "Karachay-Balkar",
+            Lang::ksh => "Kölsch",
+            Lang::lao => "Lao",
+            Lang::lat => "Latin",
+            Lang::lav => "Latvian",
+            Lang::lin => "Lingala",
+            Lang::lit => "Lithuanian",
+            Lang::liv => "Livonian",
+            Lang::lmo => "Lombard",
+            Lang::ltz => "Luxembourgish",
+            Lang::lud => "Ludian",
+            Lang::lug => "Ganda",
+            Lang::luo => "Luo",
+            Lang::lus => "Mizo",
+            Lang::mal => "Malayalam",
+            Lang::mar => "Marathi",
+            Lang::mcd => "Sharanahua",
+            Lang::mcf => "Matsés",
+            Lang::mdf => "Moksha",
+            Lang::mhr => "Eastern Mari",
+            Lang::mkd => "Macedonian",
+            Lang::mlg => "Malagasy",
+            Lang::mlt => "Maltese",
+            Lang::mns => "Mansi",
+            Lang::mon => "Mongolian",
+            Lang::mri => "Maori",
+            Lang::mrj => "Western Mari",
+            Lang::msa | Lang::msamalay => "Malay",
+            Lang::msaind => "Indonesian",
+            Lang::msamin => "Minangkabau",
+            Lang::msazsm => "Standard Malay",
+            Lang::mwl => "Mirandese",
+            Lang::mya => "Burmese",
+            Lang::myv => "Erzya",
+            Lang::nav => "Navajo",
+            Lang::nep => "Nepali",
+            Lang::nhn => "Central Nahuatl",
+            Lang::nio => "Nganasan",
+            Lang::nld => "Dutch",
+            Lang::nno => "Norwegian Nynorsk",
+            Lang::nob => "Norwegian Bokmål",
+            Lang::nso => "Northern Sotho",
+            Lang::nus => "Nuer",
+            Lang::oci => "Occitan",
+            Lang::olo => "Livvi",
+            Lang::ori => "Odia",
+            Lang::oss => "Ossetian",
+            Lang::pag => "Pangasinan",
+            Lang::pam => "Pampanga",
+            Lang::pan => "Punjabi",
+            Lang::pbt => "Pashto",
+            Lang::pes => "Persian",
+            Lang::pfl => "Palatine German",
+            Lang::pli => "Pali",
+            Lang::pms => "Piedmontese",
+            Lang::pnb => "Western Panjabi",
+            Lang::pol => "Polish",
+            Lang::pon => "Pohnpeian",
+            Lang::por => "Portuguese",
+            Lang::que => "Quechua",
+            Lang::roh => "Romansh",
+            Lang::ron => "Romanian",
+            Lang::rus => "Russian",
+            Lang::sag | Lang::sagb => "Sango",
+            Lang::sah => "Sakha",
+            Lang::sat => "Santali",
+            Lang::scn => "Sicilian",
+            Lang::sgs => "Samogitian",
+            Lang::shk => "Shilluk",
+            Lang::shn => "Shan",
+            Lang::sin => "Sinhala",
+            Lang::sjd => "Kildin Sami",
+            Lang::sjk => "Kemi Sami",
+            Lang::sju => "Ume Sami",
+            Lang::slk => "Slovak",
+            Lang::slv => "Slovenian",
+            Lang::sma => "Southern Sami",
+            Lang::sme => "Northern Sami",
+            Lang::smj => "Lule Sami",
+            Lang::smn => "Inari Sami",
+            Lang::sms => "Skolt Sami",
+            Lang::sna => "Shona",
+            Lang::snd => "Sindhi",
+            Lang::som => "Somali",
+            Lang::sot => "Southern Sotho",
+            Lang::spa => "Spanish",
+            Lang::sqi => "Albanian",
+            Lang::srd => "Sardinian",
+            Lang::swa => "Swahili",
+            Lang::swe => "Swedish",
+            Lang::tam => "Tamil",
+            Lang::tat => "Tatar",
+            Lang::tca => "Ticuna",
+            Lang::tel => "Telugu",
+            Lang::tet => "Tetum",
+            Lang::tgk => "Tajik",
+            Lang::tgl => "Tagalog",
+            Lang::tha => "Thai",
+            Lang::tir => "Tigrinya",
+            Lang::tso => "Tsonga",
+            Lang::tuk => "Turkmen",
+            Lang::tur => "Turkish",
+            Lang::twi => "Twi",
+            Lang::tzh => "Tzeltal",
+            Lang::udm => "Udmurt",
+            Lang::uig => "Uyghur",
+            Lang::ukr => "Ukrainian",
+            Lang::und | Lang::undhtml => "Undetermined",
+            Lang::ura => "Urarina",
+            Lang::urd => "Urdu",
+            Lang::uzn => "Uzbek",
+            Lang::vie => "Vietnamese",
+            Lang::vls => "West Flemish",
+            Lang::vol => "Volapük",
+            Lang::vot => "Votic",
+            Lang::war => "Waray",
+            Lang::wln => "Walloon",
+            Lang::xmf => "Mingrelian",
+            Lang::yid => "Yiddish",
+            Lang::yrk => "Nenets",
+            Lang::yor => "Yoruba",
+            Lang::zul => "Zulu",
+            Lang::zxx => "No linguistic content",
+        }
+    }
+
+    /// ISO 639-3 code of the language, without any variety suffix
+    ///
+    /// Varieties (e.g. `hbsbos` or `estvro`) return the code of the language they belong to,
+    /// which are always the first three letters of the code.
+    pub fn language_subtag(&self) -> &'static str {
+        let code: &'static str = self.into();
+        &code[..3]
+    }
+
+    /// ISO 15924 code of the script the language model has been trained on
+    ///
+    /// Languages that can be written in multiple scripts return the one predominant in the
+    /// training data. `und` returns `Zzzz` (uncoded script) and `zxx` returns `Zxxx` (unwritten).
+    pub fn script(&self) -> &'static str {
+        match self {
+            Lang::ara
+            | Lang::ckb
+            | Lang::pbt
+            | Lang::pes
+            | Lang::pnb
+            | Lang::snd
+            | Lang::uig
+            | Lang::urd => "Arab",
+            Lang::hye => "Armn",
+            Lang::asm | Lang::ben | Lang::bpy => "Beng",
+            Lang::iku => "Cans",
+            Lang::chr => "Cher",
+            Lang::abk
+            | Lang::bak
+            | Lang::bel
+            | Lang::bul
+            | Lang::che
+            | Lang::chv
+            | Lang::hbssrp
+            | Lang::kaz
+            | Lang::kbd
+            | Lang::kca
+            | Lang::kir
+            | Lang::koi
+            | Lang::kpv
+            | Lang::krc
+            | Lang::mdf
+            | Lang::mhr
+            | Lang::mkd
+            | Lang::mns
+            | Lang::mon
+            | Lang::mrj
+            | Lang::myv
+            | Lang::nio
+            | Lang::oss
+            | Lang::rus
+            | Lang::sah
+            | Lang::sjd
+            | Lang::tat
+            | Lang::tgk
+            | Lang::udm
+            | Lang::ukr
+            | Lang::yrk => "Cyrl",
+            Lang::gom | Lang::hin | Lang::mar | Lang::nep | Lang::pli => "Deva",
+            Lang::amh | Lang::tir => "Ethi",
+            Lang::kat | Lang::xmf => "Geor",
+            Lang::ell => "Grek",
+            Lang::guj => "Gujr",
+            Lang::pan => "Guru",
+            Lang::kor => "Hang",
+            Lang::cdo | Lang::cmn => "Hani",
+            Lang::heb | Lang::yid => "Hebr",
+            Lang::jpn => "Jpan",
+            Lang::khm => "Khmr",
+            Lang::kan => "Knda",
+            Lang::lao => "Laoo",
+            Lang::mal => "Mlym",
+            Lang::mya | Lang::shn => "Mymr",
+            Lang::sat => "Olck",
+            Lang::ori => "Orya",
+            Lang::sin => "Sinh",
+            Lang::aii => "Syrc",
+            Lang::tam => "Taml",
+            Lang::tel => "Telu",
+            Lang::div => "Thaa",
+            Lang::tha => "Thai",
+            Lang::bod => "Tibt",
+            Lang::zxx => "Zxxx",
+            Lang::und | Lang::undhtml => "Zzzz",
+            _ => "Latn",
+        }
+    }
+
+    /// Whether the script of the language is written from right to left
+    pub fn is_rtl(&self) -> bool {
+        matches!(
+            self.script(),
+            "Adlm" | "Arab" | "Hebr" | "Nkoo" | "Rohg" | "Samr" | "Syrc" | "Thaa"
+        )
+    }
+
+    /// Family, or branch for the large families, of the language
+    pub fn family(&self) -> LangFamily {
+        match self {
+            Lang::ace
+            | Lang::adz
+            | Lang::bcl
+            | Lang::ceb
+            | Lang::chk
+            | Lang::fij
+            | Lang::hmo
+            | Lang::ilo
+            | Lang::mlg
+            | Lang::mri
+            | Lang::msa
+            | Lang::msaind
+            | Lang::msamalay
+            | Lang::msamin
+            | Lang::msazsm
+            | Lang::pag
+            | Lang::pam
+            | Lang::pon
+            | Lang::tet
+            | Lang::tgl
+            | Lang::war => LangFamily::Austronesian,
+            Lang::lav | Lang::lit | Lang::sgs => LangFamily::Baltic,
+            Lang::bre | Lang::cor | Lang::cym | Lang::gla | Lang::gle | Lang::glv => {
+                LangFamily::Celtic
+            }
+            Lang::kan | Lang::mal | Lang::tam | Lang::tel => LangFamily::Dravidian,
+            Lang::afr
+            | Lang::bar
+            | Lang::dan
+            | Lang::deu
+            | Lang::eng
+            | Lang::fao
+            | Lang::fry
+            | Lang::gsw
+            | Lang::isl
+            | Lang::ksh
+            | Lang::ltz
+            | Lang::nld
+            | Lang::nno
+            | Lang::nob
+            | Lang::pfl
+            | Lang::swe
+            | Lang::vls
+            | Lang::yid => LangFamily::Germanic,
+            Lang::asm
+            | Lang::ben
+            | Lang::bpy
+            | Lang::ckb
+            | Lang::diq
+            | Lang::div
+            | Lang::gom
+            | Lang::guj
+            | Lang::hin
+            | Lang::kmr
+            | Lang::mar
+            | Lang::nep
+            | Lang::ori
+            | Lang::oss
+            | Lang::pan
+            | Lang::pbt
+            | Lang::pes
+            | Lang::pli
+            | Lang::pnb
+            | Lang::sin
+            | Lang::snd
+            | Lang::tgk
+            | Lang::urd => LangFamily::IndoIranian,
+            Lang::jpn => LangFamily::Japonic,
+            Lang::kor => LangFamily::Koreanic,
+            Lang::ewe
+            | Lang::fon
+            | Lang::ibo
+            | Lang::kbp
+            | Lang::lin
+            | Lang::lug
+            | Lang::nso
+            | Lang::sag
+            | Lang::sagb
+            | Lang::sna
+            | Lang::sot
+            | Lang::swa
+            | Lang::tso
+            | Lang::twi
+            | Lang::yor
+            | Lang::zul => LangFamily::NigerCongo,
+            Lang::cat
+            | Lang::cos
+            | Lang::ext
+            | Lang::fra
+            | Lang::glg
+            | Lang::hat
+            | Lang::ita
+            | Lang::lat
+            | Lang::lmo
+            | Lang::mwl
+            | Lang::oci
+            | Lang::pms
+            | Lang::por
+            | Lang::roh
+            | Lang::ron
+            | Lang::scn
+            | Lang::spa
+            | Lang::srd
+            | Lang::wln => LangFamily::Romance,
+            Lang::aii | Lang::amh | Lang::ara | Lang::heb | Lang::mlt | Lang::tir => {
+                LangFamily::Semitic
+            }
+            Lang::bod | Lang::cdo | Lang::cmn | Lang::cnh | Lang::kac | Lang::lus | Lang::mya => {
+                LangFamily::SinoTibetan
+            }
+            Lang::bel
+            | Lang::bul
+            | Lang::ces
+            | Lang::hbs
+            | Lang::hbsbos
+            | Lang::hbshrv
+            | Lang::hbssrp
+            | Lang::hsb
+            | Lang::mkd
+            | Lang::pol
+            | Lang::rus
+            | Lang::slk
+            | Lang::slv
+            | Lang::ukr => LangFamily::Slavic,
+            Lang::aze
+            | Lang::bak
+            | Lang::chv
+            | Lang::kaz
+            | Lang::kir
+            | Lang::krc
+            | Lang::sah
+            | Lang::tat
+            | Lang::tuk
+            | Lang::tur
+            | Lang::uig
+            | Lang::uzn => LangFamily::Turkic,
+            Lang::est
+            | Lang::estvro
+            | Lang::fin
+            | Lang::fini
+            | Lang::fink
+            | Lang::finl
+            | Lang::finm
+            | Lang::fino
+            | Lang::finp
+            | Lang::finr
+            | Lang::fins
+            | Lang::fint
+            | Lang::finx
+            | Lang::hun
+            | Lang::izh
+            | Lang::kca
+            | Lang::koi
+            | Lang::kpv
+            | Lang::liv
+            | Lang::lud
+            | Lang::mdf
+            | Lang::mhr
+            | Lang::mns
+            | Lang::mrj
+            | Lang::myv
+            | Lang::nio
+            | Lang::olo
+            | Lang::sjd
+            | Lang::sjk
+            | Lang::sju
+            | Lang::sma
+            | Lang::sme
+            | Lang::smj
+            | Lang::smn
+            | Lang::sms
+            | Lang::udm
+            | Lang::vot
+            | Lang::yrk => LangFamily::Uralic,
+            _ => LangFamily::Other,
+        }
+    }
+
+    // Varieties that have their own registered language subtag
+    const BCP47_VARIETIES: [(&'static str, Lang); 8] = [
+        ("bos", Lang::hbsbos),
+        ("hrv", Lang::hbshrv),
+        ("srp", Lang::hbssrp),
+        ("ind", Lang::msaind),
+        ("zsm", Lang::msazsm),
+        ("min", Lang::msamin),
+        ("zlm", Lang::msamalay),
+        ("vro", Lang::estvro),
+    ];
+
+    /// BCP-47 representation of the language, composed of the language code and the script
+    ///
+    /// Varieties without a registered subtag use the code of the language they belong to
+    /// and their own code as private use subtag (e.g. `fin-Latn-x-fini`).
+    pub fn to_bcp47(&self) -> String {
+        if let Some((subtag, _)) = Self::BCP47_VARIETIES.iter().find(|(_, l)| l == self) {
+            return format!("{subtag}-{}", self.script());
+        }
+        let collapsed = self.collapse();
+        if collapsed != *self {
+            return format!("{collapsed}-{}-x-{self}", self.script());
+        }
+        format!("{}-{}", self, self.script())
+    }
+
+    /// Parse a BCP-47 language tag
+    ///
+    /// Both hyphen and underscore are accepted as separators. Region and variant subtags are
+    /// ignored, but the script, if present, has to match the one used by the language.
+    pub fn from_bcp47(s: &str) -> Result<Self> {
+        // Common aliases that do not match any of the language codes
+        const ALIASES: [(&str, Lang); 7] = [
+            ("zh", Lang::cmn),
+            ("zho", Lang::cmn),
+            ("chi", Lang::cmn),
+            ("fas", Lang::pes),
+            ("per", Lang::pes),
+            ("may", Lang::msa),
+            ("sh", Lang::hbs),
+        ];
+
+        let mut subtags = s.split(['-', '_']);
+        let code = subtags.next().unwrap_or_default().to_lowercase();
+        let mut lang = match ALIASES
+            .iter()
+            .chain(&Self::BCP47_VARIETIES)
+            .find(|(alias, _)| *alias == code)
+        {
+            Some((_, lang)) => *lang,
+            None => Lang::from_str(&code)
+                .with_context(|| format!("Unknown language subtag '{code}' in tag '{s}'"))?,
+        };
+
+        // Private use subtags after 'x' are not part of the tag itself
+        let subtags: Vec<_> = subtags.collect();
+        let (subtags, private) = match subtags.iter().position(|t| t.eq_ignore_ascii_case("x")) {
+            Some(pos) => (&subtags[..pos], &subtags[pos + 1..]),
+            None => (&subtags[..], &[][..]),
+        };
+        // A variety of the language can be given as private use subtag
+        if let Some(variety) = private
+            .iter()
+            .find_map(|t| Lang::from_str(&t.to_lowercase()).ok())
+            .filter(|variety| variety.collapse() == lang)
+        {
+            lang = variety;
+        }
+
+        // Script is the only subtag with four letters
+        if let Some(script) = subtags
+            .iter()
+            .find(|t| t.len() == 4 && t.chars().all(char::is_alphabetic))
+        {
+            let accepted: &[&str] = match lang.script() {
+                "Hani" => &["Hani", "Hans", "Hant"],
+                "Jpan" => &["Jpan", "Hani", "Hira", "Kana"],
+                "Hang" => &["Hang", "Kore"],
+                expected => &[expected],
+            };
+            if !accepted.iter().any(|a| a.eq_ignore_ascii_case(script)) {
+                bail!("Script '{script}' in tag '{s}' is not supported for language '{lang}'");
+            }
+        }
+
+        Ok(lang)
+    }
 }
 
 /**
@@ -308,6 +948,7 @@ impl Lang {
  */
 macro_rules! lang_scores {
     ($name: ident, $lang: ident, $size: expr) => {
+        #[derive(Clone)]
         pub struct $name {
             inner: [f32; $size],
         }
@@ -337,6 +978,40 @@ macro_rules! lang_scores {
                 }
             }
 
+            // Same as add, but processing chunks of 8 values
+            // so the compiler can use 256-bit vector instructions (std::simd is not stable)
+            // the remainder is added one by one
+            pub fn add_simd(&mut self, other: &Self) {
+                let mut chunks = self.inner.chunks_exact_mut(8);
+                let mut other_chunks = other.inner.chunks_exact(8);
+                for (chunk, other_chunk) in (&mut chunks).zip(&mut other_chunks) {
+                    for (val, other) in chunk.iter_mut().zip(other_chunk) {
+                        *val += other;
+                    }
+                }
+                let remainder = chunks.into_remainder();
+                for (val, other) in remainder.iter_mut().zip(other_chunks.remainder()) {
+                    *val += other;
+                }
+            }
+
+            // Add a value to all the positions that are not set in the mask,
+            // processing chunks of 8 values like add_simd
+            pub fn add_unset(&mut self, value: f32, mask: &[bool; $size]) {
+                let mut chunks = self.inner.chunks_exact_mut(8);
+                let mut mask_chunks = mask.chunks_exact(8);
+                // multiply by the negated mask instead of branching
+                for (chunk, mask_chunk) in (&mut chunks).zip(&mut mask_chunks) {
+                    for (val, set) in chunk.iter_mut().zip(mask_chunk) {
+                        *val += value * !*set as u8 as f32;
+                    }
+                }
+                let remainder = chunks.into_remainder();
+                for (val, set) in remainder.iter_mut().zip(mask_chunks.remainder()) {
+                    *val += value * !*set as u8 as f32;
+                }
+            }
+
             // Normalize scores dividing by a given value
             pub fn norm(&mut self, y: f32) {
                 for i in 0..$size {
@@ -364,6 +1039,56 @@ macro_rules! lang_scores {
                     .map(|lang| (lang.to_string(), self.inner[lang as usize]))
                     .collect()
             }
+
+            // Iterate over all the variants and their values, in the order of the enum
+            pub fn iter(&self) -> <&Self as IntoIterator>::IntoIter {
+                self.into_iter()
+            }
+
+            // Return the n variants with the lowest values, sorted in ascending order
+            // special variants and variants without a score (0) are skipped
+            pub fn top_n(&self, n: usize) -> Vec<($lang, f32)> {
+                let mut sorted: Vec<_> = self
+                    .iter()
+                    .filter(|(lang, value)| !lang.is_special() && *value != 0.0)
+                    .collect();
+                sorted.sort_by(|a, b| a.1.total_cmp(&b.1));
+                sorted.truncate(n);
+                sorted
+            }
+
+            // Convert scores (lower is better) to a probability distribution
+            // over the non-special variants with the softmax of the negated values
+            pub fn softmax(&self) -> Vec<($lang, f64)> {
+                let langs: Vec<_> = $lang::iter().filter(|lang| !lang.is_special()).collect();
+                // Subtract the maximum (minimum score) to avoid overflow
+                let best = langs
+                    .iter()
+                    .map(|lang| -self.inner[*lang as usize] as f64)
+                    .fold(f64::NEG_INFINITY, f64::max);
+                let exps: Vec<f64> = langs
+                    .iter()
+                    .map(|lang| (-self.inner[*lang as usize] as f64 - best).exp())
+                    .collect();
+                let sum: f64 = exps.iter().sum();
+                langs
+                    .into_iter()
+                    .zip(exps)
+                    .map(|(lang, exp)| (lang, exp / sum))
+                    .collect()
+            }
+        }
+
+        impl<'a> IntoIterator for &'a $name {
+            type Item = ($lang, f32);
+            type IntoIter = iter::Zip<
+                <$lang as IntoEnumIterator>::Iterator,
+                iter::Copied<slice::Iter<'a, f32>>,
+            >;
+
+            fn into_iter(self) -> Self::IntoIter {
+                $lang::iter().zip(self.inner.iter().copied())
+            }
         }
 
         impl fmt::Debug for $name {
@@ -382,7 +1107,30 @@ macro_rules! lang_scores {
 }
 
 macro_rules! lang_bitmap {
+    // Element-wise binary operator, implemented for owned values and references
+    // the operator token is the assignment version of the operator
+    (@binop $name: ident, $trait: ident, $method: ident, $op: tt) => {
+        impl $trait for $name {
+            type Output = Self;
+
+            fn $method(mut self, other: Self) -> Self {
+                for (val, other) in self.inner.iter_mut().zip(other.inner) {
+                    *val $op other;
+                }
+                self
+            }
+        }
+
+        impl $trait for &$name {
+            type Output = $name;
+
+            fn $method(self, other: Self) -> $name {
+                self.clone().$method(other.clone())
+            }
+        }
+    };
     ($name: ident, $lang: ident, $size: expr) => {
+        #[derive(Clone, PartialEq, Eq)]
         pub struct $name {
             inner: [bool; $size],
         }
@@ -394,6 +1142,22 @@ macro_rules! lang_bitmap {
                 }
             }
 
+            // Create a bitmap with all the variants set to true
+            pub fn new_all_set() -> Self {
+                Self {
+                    inner: [true; $size],
+                }
+            }
+
+            // Create a bitmap with only the given variants set to true
+            pub fn from_langs(langs: &[$lang]) -> Self {
+                let mut bitmap = Self::new();
+                for lang in langs {
+                    bitmap.set(lang, true);
+                }
+                bitmap
+            }
+
             pub fn get(&self, lang: &$lang) -> bool {
                 self.inner[*lang as usize]
             }
@@ -408,6 +1172,49 @@ macro_rules! lang_bitmap {
                     self.inner[i] = false;
                 }
             }
+
+            // Iterate over the variants that are set to true
+            pub fn iter_set(&self) -> impl Iterator<Item = $lang> + '_ {
+                self.inner
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, val)| **val)
+                    .map(|(i, _)| $lang::from_repr(i as u8).unwrap())
+            }
+
+            // Raw values of the bitmap, in the order of the enum
+            pub fn as_array(&self) -> &[bool; $size] {
+                &self.inner
+            }
+
+            // Number of variants that are set to true
+            pub fn count_set(&self) -> usize {
+                // bools are stored as bytes, so there is no count_ones to take advantage of
+                self.inner.iter().filter(|val| **val).count()
+            }
+        }
+
+        lang_bitmap!(@binop $name, BitAnd, bitand, &=);
+        lang_bitmap!(@binop $name, BitOr, bitor, |=);
+        lang_bitmap!(@binop $name, BitXor, bitxor, ^=);
+
+        impl Not for $name {
+            type Output = Self;
+
+            fn not(mut self) -> Self {
+                for val in self.inner.iter_mut() {
+                    *val = !*val;
+                }
+                self
+            }
+        }
+
+        impl Not for &$name {
+            type Output = $name;
+
+            fn not(self) -> $name {
+                !self.clone()
+            }
         }
 
         impl Index<usize> for $name {
@@ -433,5 +1240,125 @@ macro_rules! lang_bitmap {
     };
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for Lang {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.into())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Lang {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let code = String::deserialize(deserializer)?;
+        Lang::from_str(&code).map_err(serde::de::Error::custom)
+    }
+}
+
 lang_scores!(LangScores, Lang, Lang::COUNT);
 lang_bitmap!(LangBitmap, Lang, Lang::COUNT);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bcp47() {
+        assert_eq!(Lang::eng.to_bcp47(), "eng-Latn");
+        assert_eq!(Lang::hbssrp.to_bcp47(), "srp-Cyrl");
+        assert_eq!(Lang::msaind.to_bcp47(), "ind-Latn");
+        assert_eq!(Lang::estvro.to_bcp47(), "vro-Latn");
+        assert_eq!(Lang::fini.to_bcp47(), "fin-Latn-x-fini");
+        assert_eq!(Lang::undhtml.to_bcp47(), "und-Zzzz-x-undhtml");
+        for lang in Lang::iter() {
+            assert_eq!(Lang::from_bcp47(&lang.to_bcp47()).unwrap(), lang);
+        }
+        assert_eq!(Lang::from_bcp47("eng_Latn").unwrap(), Lang::eng);
+        assert_eq!(Lang::from_bcp47("zh-Hans").unwrap(), Lang::cmn);
+        assert_eq!(Lang::from_bcp47("zh-hant-TW").unwrap(), Lang::cmn);
+        assert_eq!(Lang::from_bcp47("spa-ES").unwrap(), Lang::spa);
+        assert!(Lang::from_bcp47("rus-Latn").is_err());
+        assert!(Lang::from_bcp47("xx-Latn").is_err());
+    }
+
+    #[test]
+    fn test_human_name() {
+        assert_eq!(Lang::abk.human_name(), "Abkhazian");
+        assert_eq!(Lang::cmn.human_name(), "Mandarin Chinese");
+        assert_eq!(Lang::spa.human_name(), "Spanish");
+        assert_eq!(Lang::hbssrp.human_name(), "Serbian");
+        assert_eq!(Lang::zxx.human_name(), "No linguistic content");
+        // Varieties share the name of their language instead of showing their code
+        assert_eq!(Lang::fini.human_name(), "Finnish");
+        assert_eq!(Lang::msamalay.human_name(), "Malay");
+        for lang in Lang::iter() {
+            assert!(!lang.human_name().contains('('), "{lang}");
+        }
+    }
+
+    #[test]
+    fn test_bitmap_ops() {
+        let a = LangBitmap::from_langs(&[Lang::cat, Lang::spa]);
+        let b = LangBitmap::from_langs(&[Lang::spa, Lang::por]);
+
+        assert_eq!((&a & &b).iter_set().collect::<Vec<_>>(), [Lang::spa]);
+        assert_eq!(
+            (&a | &b).iter_set().collect::<Vec<_>>(),
+            [Lang::cat, Lang::por, Lang::spa]
+        );
+        assert_eq!(
+            (&a ^ &b).iter_set().collect::<Vec<_>>(),
+            [Lang::cat, Lang::por]
+        );
+        assert_eq!((!&a).count_set(), Lang::COUNT - 2);
+        assert!(!a & LangBitmap::new_all_set() == !LangBitmap::from_langs(&[Lang::cat, Lang::spa]));
+    }
+
+    #[test]
+    fn test_softmax() {
+        let mut scores = LangScores::new();
+        for lang in Lang::iter() {
+            scores.insert(lang, 7.0);
+        }
+        scores.insert(Lang::spa, 1.0);
+        scores.insert(Lang::und, 0.0);
+
+        let probs = scores.softmax();
+        assert_eq!(probs.len(), Lang::COUNT - 2);
+        let total: f64 = probs.iter().map(|(_, p)| p).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        let (best, _) = probs.iter().max_by(|a, b| a.1.total_cmp(&b.1)).unwrap();
+        assert_eq!(*best, Lang::spa);
+    }
+
+    #[test]
+    fn test_top_n() {
+        let mut scores = LangScores::new();
+        scores.insert(Lang::spa, 2.0);
+        scores.insert(Lang::cat, 1.5);
+        scores.insert(Lang::glg, 3.0);
+        scores.insert(Lang::zxx, 0.5);
+
+        assert_eq!(scores.top_n(2), [(Lang::cat, 1.5), (Lang::spa, 2.0)]);
+        assert_eq!(scores.top_n(10).len(), 3);
+    }
+
+    #[test]
+    fn test_simd() {
+        let mut scores = LangScores::new();
+        let mut other = LangScores::new();
+        for (i, lang) in Lang::iter().enumerate() {
+            scores.insert(lang, i as f32);
+            other.insert(lang, 1.5);
+        }
+        let mut expected = scores.clone();
+        expected.add(&other);
+        scores.add_simd(&other);
+        assert!(scores.iter().zip(&expected).all(|(a, b)| a == b));
+
+        let mask = LangBitmap::from_langs(&[Lang::cat, Lang::und]);
+        scores.add_unset(7.0, mask.as_array());
+        assert_eq!(scores.get(Lang::cat), expected.get(Lang::cat));
+        assert_eq!(scores.get(Lang::zxx), expected.get(Lang::zxx) + 7.0);
+    }
+}
@@ -1,13 +1,15 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::fs::{self, File};
 use std::hash::BuildHasherDefault;
-use std::io::{self, Read, Write};
+use std::io::{self, BufWriter, Read, Write};
+use std::mem;
 use std::ops::Index;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::thread;
+use std::sync::OnceLock;
 
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use bitcode;
 use log::{debug, info, warn};
 use rayon::prelude::*;
@@ -22,6 +24,11 @@ use crate::lang::{Lang, LangBitmap, LangScores};
 #[derive(
     bitcode::Encode, bitcode::Decode, EnumIter, Display, EnumCount, Debug, PartialEq, Clone, Copy,
 )]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
 #[strum(serialize_all = "lowercase")]
 pub enum OrderNgram {
     Word,
@@ -42,11 +49,79 @@ pub struct ModelNgram {
 impl ModelNgram {
     // The following values are the ones used in Jauhiainen et al. 2017.
     pub const MAX_USED: f64 = 0.0000005;
+    // Header of the binary models
+    // version has to be increased every time the binary format changes
+    pub const FORMAT_MAGIC: [u8; 4] = *b"HLPM";
+    pub const FORMAT_VERSION: u16 = 1;
+    // Magic number at the beginning of zstd frames
+    const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+    /// Create an empty model
+    pub fn new(model_type: OrderNgram) -> Self {
+        Self {
+            dic: HashMap::default(),
+            model_type,
+        }
+    }
 
     pub fn contains(&self, key: &str) -> bool {
         self.dic.contains_key(key)
     }
 
+    /// Iterate over the grams and the probabilities of each language
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[(Lang, f32)])> {
+        self.dic
+            .iter()
+            .map(|(gram, probs)| (gram.as_str(), probs.as_slice()))
+    }
+
+    /// Number of grams in the model
+    pub fn vocab_size(&self) -> usize {
+        self.dic.len()
+    }
+
+    /// Number of distinct languages that have at least one gram in the model
+    pub fn lang_count(&self) -> usize {
+        let mut langs = LangBitmap::new();
+        for probs in self.dic.values() {
+            for (lang, _) in probs {
+                langs.set(lang, true);
+            }
+        }
+        langs.count_set()
+    }
+
+    /// Number of grams that have a probability for a given language
+    pub fn gram_count_for_lang(&self, lang: Lang) -> usize {
+        self.dic
+            .values()
+            .filter(|probs| probs.iter().any(|(l, _)| *l == lang))
+            .count()
+    }
+
+    /// Create a new model without the grams that are rare in all the languages
+    ///
+    /// Probabilities are stored as negative logarithms, so a gram is removed
+    /// when all of its values are above the threshold.
+    pub fn prune(&self, threshold: f32) -> ModelNgram {
+        let dic: HashMap<_, _, MyHasher> = self
+            .dic
+            .iter()
+            .filter(|(_, probs)| probs.iter().any(|(_, prob)| *prob <= threshold))
+            .map(|(gram, probs)| (gram.clone(), probs.clone()))
+            .collect();
+        debug!(
+            "{}: pruned {} entries",
+            self.model_type,
+            self.dic.len() - dic.len()
+        );
+
+        ModelNgram {
+            dic,
+            model_type: self.model_type,
+        }
+    }
+
     pub fn from_text(
         model_dir: &Path,
         model_type: OrderNgram,
@@ -123,6 +198,8 @@ impl ModelNgram {
         // Read the language model file to a string all at once
         let modelfile =
             fs::read_to_string(p).with_context(|| format!("Error reading file: {p:?}"))?;
+        // Remove byte order mark, Windows editors may add it
+        let modelfile = modelfile.strip_prefix('\u{FEFF}').unwrap_or(&modelfile);
 
         let mut temp_dict: HashMap<_, _, MyHasher> = HashMap::default();
         let mut num_features = 0_u64;
@@ -175,31 +252,331 @@ impl ModelNgram {
         Ok(())
     }
 
+    /// Create a new model containing only the given languages
+    ///
+    /// Grams that do not have probabilities for any of the languages are removed.
+    pub fn filter_langs(&self, langs: &[Lang]) -> ModelNgram {
+        let allowed = LangBitmap::from_langs(langs);
+        let dic = self
+            .dic
+            .iter()
+            .filter_map(|(gram, probs)| {
+                let probs: Vec<_> = probs
+                    .iter()
+                    .filter(|(lang, _)| allowed.get(lang))
+                    .cloned()
+                    .collect();
+                (!probs.is_empty()).then(|| (gram.clone(), probs))
+            })
+            .collect();
+
+        ModelNgram {
+            dic,
+            model_type: self.model_type,
+        }
+    }
+
+    /// Combine two models of the same order into a new one
+    ///
+    /// Probabilities of languages that are present in a gram of both models are averaged,
+    /// the rest are kept as they are.
+    pub fn merge(self, other: ModelNgram) -> Result<ModelNgram> {
+        if self.model_type != other.model_type {
+            bail!(
+                "Cannot merge models of different types: '{}' and '{}'",
+                self.model_type,
+                other.model_type
+            );
+        }
+
+        let mut dic = self.dic;
+        for (gram, other_probs) in other.dic {
+            let Some(probs) = dic.get_mut(&gram) else {
+                dic.insert(gram, other_probs);
+                continue;
+            };
+            for (lang, other_prob) in other_probs {
+                if let Some((_, prob)) = probs.iter_mut().find(|(l, _)| *l == lang) {
+                    *prob = (*prob + other_prob) / 2.0;
+                } else {
+                    probs.push((lang, other_prob));
+                }
+            }
+        }
+
+        Ok(ModelNgram {
+            dic,
+            model_type: self.model_type,
+        })
+    }
+
+    /// Linearly interpolate the probabilities of two models of the same order into a new one
+    ///
+    /// Probabilities of languages present in a gram of both models are interpolated
+    /// with `1 - weight` for this model and `weight` for the other one,
+    /// the rest are kept as they are.
+    pub fn interpolate(&self, other: &ModelNgram, weight: f32) -> Result<ModelNgram> {
+        if self.model_type != other.model_type {
+            bail!(
+                "Cannot interpolate models of different types: '{}' and '{}'",
+                self.model_type,
+                other.model_type
+            );
+        }
+        if !(0.0..=1.0).contains(&weight) {
+            bail!("Interpolation weight must be between 0 and 1, got '{weight}'");
+        }
+
+        // Probabilities are stored as negative logarithms, interpolate the actual probabilities
+        let weight = weight as f64;
+        let interpolate = |prob: f32, other_prob: f32| {
+            let mixed = (1.0 - weight) * 10_f64.powf(-prob as f64)
+                + weight * 10_f64.powf(-other_prob as f64);
+            -mixed.log10() as f32
+        };
+
+        let mut dic = self.dic.clone();
+        for (gram, other_probs) in &other.dic {
+            let Some(probs) = dic.get_mut(gram) else {
+                dic.insert(gram.clone(), other_probs.clone());
+                continue;
+            };
+            for (lang, other_prob) in other_probs {
+                if let Some((_, prob)) = probs.iter_mut().find(|(l, _)| l == lang) {
+                    *prob = interpolate(*prob, *other_prob);
+                } else {
+                    probs.push((*lang, *other_prob));
+                }
+            }
+        }
+
+        Ok(ModelNgram {
+            dic,
+            model_type: self.model_type,
+        })
+    }
+
+    /// Create a model of a single language from an ARPA language model file,
+    /// like the ones produced by SRILM or KenLM
+    ///
+    /// The word order is read from the unigrams section, where each token is a word.
+    /// Character orders are read from the section of the same order,
+    /// where each token is a character and sentence boundaries (`<s>`, `</s>`)
+    /// are the spaces around words, so the model has to be trained with one word per line.
+    /// Log probabilities are used as they are, conditional for orders higher than one.
+    pub fn from_arpa(path: &Path, lang: Lang, order: OrderNgram) -> Result<Self> {
+        let arpa = fs::read_to_string(path)
+            .with_context(|| format!("Could not open ARPA file '{}'", path.display()))?;
+        let n = (order as usize).max(1);
+        let section = format!("\\{n}-grams:");
+
+        let mut dic: HashMap<String, Vec<(Lang, f32)>, MyHasher> = HashMap::default();
+        let mut lines = arpa.lines().enumerate();
+        if !lines.any(|(_, line)| line.trim() == section) {
+            bail!("Section '{section}' not found in '{}'", path.display());
+        }
+        let mut skipped = 0;
+        for (i, line) in lines {
+            let line = line.trim();
+            if line.starts_with('\\') {
+                break;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            // Log probability, the n tokens and an optional backoff weight
+            let mut fields = line.split_whitespace();
+            let logprob: f32 =
+                fields.next().unwrap_or_default().parse().with_context(|| {
+                    format!("Error parsing line {} in '{}'", i + 1, path.display())
+                })?;
+            let tokens: Vec<_> = fields.take(n).collect();
+            if tokens.len() != n {
+                bail!(
+                    "Expected {n} tokens in line {} of '{}', found {}",
+                    i + 1,
+                    path.display(),
+                    tokens.len()
+                );
+            }
+            // -99 is used for events that never happen, like the sentence start
+            if logprob <= -99.0 {
+                continue;
+            }
+
+            let gram = if order == OrderNgram::Word {
+                tokens[0].to_string()
+            } else {
+                let mut gram = String::new();
+                for token in tokens {
+                    match token {
+                        "<s>" | "</s>" => gram.push(' '),
+                        _ if token.chars().count() == 1 => gram.push_str(token),
+                        _ => break,
+                    }
+                }
+                gram
+            };
+            if ["<s>", "</s>", "<unk>"].contains(&gram.as_str())
+                || (order != OrderNgram::Word && gram.chars().count() != n)
+            {
+                skipped += 1;
+                continue;
+            }
+            // Keep the most probable if different tokens end up being the same gram
+            let prob = -logprob;
+            dic.entry(gram)
+                .and_modify(|probs| probs[0].1 = probs[0].1.min(prob))
+                .or_insert_with(|| vec![(lang, prob)]);
+        }
+        debug!("{}: skipped {skipped} ARPA entries", path.display());
+
+        Ok(ModelNgram {
+            dic,
+            model_type: order,
+        })
+    }
+
     // Create a new struct reading from a binary file
     pub fn from_bin(p: &Path) -> Result<Self> {
-        let mut file = File::open(p)
+        let file = File::open(p)
             .with_context(|| format!("Could not open model file '{}'", p.display()))?;
+
+        Self::from_reader(file).with_context(|| format!("Error reading model '{}'", p.display()))
+    }
+
+    /// Create a new struct reading from a zstd compressed binary file
+    pub fn from_compressed(p: &Path) -> Result<Self> {
+        // Compression is detected when reading, this is here for symmetry with save_compressed
+        Self::from_bin(p)
+    }
+
+    // Check the entries of the model for the given order,
+    // setting in `langs` the languages that have entries
+    fn verify(&self, order: OrderNgram, langs: &mut LangBitmap) -> Result<Vec<String>> {
+        let mut warnings = Vec::new();
+        if self.model_type != order {
+            bail!(
+                "Model in position of '{order}' has type '{}'",
+                self.model_type
+            );
+        }
+        if self.dic.is_empty() {
+            warnings.push(format!("Model '{order}' does not have any entry"));
+        }
+        for (gram, probs) in &self.dic {
+            for (lang, prob) in probs {
+                if !prob.is_finite() {
+                    bail!("Model '{order}' has an invalid probability for '{lang}' in gram '{gram}': {prob}");
+                }
+                langs.set(lang, true);
+            }
+        }
+        Ok(warnings)
+    }
+
+    /// Create a new struct reading the binary format from any reader
+    ///
+    /// Zstd compressed models are detected and decompressed automatically.
+    pub fn from_reader(mut r: impl Read) -> Result<Self> {
         let mut content = Vec::new();
-        let _ = file
+        let _ = r
             .read_to_end(&mut content)
-            .with_context(|| format!("Error during reading file '{}'", p.display()))?;
+            .with_context(|| "Error during reading model bytes")?;
+
+        Self::from_slice(&content)
+    }
 
+    // Decode the binary format from a slice of bytes
+    fn from_slice(content: &[u8]) -> Result<Self> {
+        let version = Self::format_version(content)?;
+        if version != Self::FORMAT_VERSION {
+            bail!(
+                "model format version {version} is not supported by this library version (expects version {})",
+                Self::FORMAT_VERSION
+            );
+        }
+        let payload = &content[Self::FORMAT_MAGIC.len() + 2..];
         // should find a way to propagate possible bitcode errors?
-        Ok(bitcode::decode(&content).with_context(|| "Could not deserialize model")?)
+        if payload.starts_with(&Self::ZSTD_MAGIC) {
+            let decompressed =
+                zstd::decode_all(payload).with_context(|| "Could not decompress model")?;
+            Ok(bitcode::decode(&decompressed).with_context(|| "Could not deserialize model")?)
+        } else {
+            Ok(bitcode::decode(payload).with_context(|| "Could not deserialize model")?)
+        }
+    }
+
+    /// Obtain the format version from the header of a binary model
+    pub fn format_version(content: &[u8]) -> Result<u16> {
+        let Some(version) = content.strip_prefix(&Self::FORMAT_MAGIC) else {
+            bail!("Not a heliport binary model or it has been created with an older version, please binarize it again");
+        };
+        let Some(version) = version.first_chunk::<2>() else {
+            bail!("Binary model header is incomplete");
+        };
+        Ok(u16::from_le_bytes(*version))
+    }
+
+    // Write magic bytes and format version at the beginning of the binary model
+    fn write_header(w: &mut impl Write) -> io::Result<()> {
+        w.write_all(&Self::FORMAT_MAGIC)?;
+        w.write_all(&Self::FORMAT_VERSION.to_le_bytes())
     }
 
     // Save the struct in binary format
     // take ownership of the struct
     pub fn save(self, p: &Path) -> Result<()> {
         // Create file
-        let mut file = File::create(p)
+        let file = File::create(p)
             .with_context(|| format!("Could not open file for saving model: {}", p.display()))?;
 
-        let serialized = bitcode::encode(&self);
-        // Write serialized bytes to the compressor
-        file.write_all(&serialized)
+        self.to_writer(file)
             .with_context(|| format!("Error during writing file '{}'", p.display()))
     }
+
+    // Save the struct in zstd compressed binary format
+    // take ownership of the struct
+    pub fn save_compressed(self, p: &Path) -> Result<()> {
+        let mut file = File::create(p)
+            .with_context(|| format!("Could not open file for saving model: {}", p.display()))?;
+
+        // Header is not compressed, so the version can be checked without decompressing
+        Self::write_header(&mut file)?;
+        // Level 0 means zstd default level
+        let mut encoder = zstd::Encoder::new(file, 0)?;
+        encoder
+            .write_all(&bitcode::encode(&self))
+            .with_context(|| format!("Error during writing file '{}'", p.display()))?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    /// Write the struct in binary format to any writer
+    pub fn to_writer(&self, mut w: impl Write) -> Result<()> {
+        Self::write_header(&mut w)?;
+        let serialized = bitcode::encode(self);
+        w.write_all(&serialized)?;
+        Ok(w.flush()?)
+    }
+
+    /// Write the dictionary as tab separated `gram\tlang\tprobability` rows,
+    /// sorted by gram and then by language code.
+    ///
+    /// Probabilities are written as they are stored, as negative logarithms.
+    pub fn to_tsv_writer(&self, w: impl Write) -> Result<()> {
+        let mut w = BufWriter::new(w);
+        let mut grams: Vec<_> = self.iter().collect();
+        grams.sort_unstable_by_key(|(gram, _)| *gram);
+        for (gram, probs) in grams {
+            let mut probs: Vec<_> = probs.iter().map(|(l, p)| (l.to_string(), p)).collect();
+            probs.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+            for (lang, prob) in probs {
+                writeln!(w, "{gram}\t{lang}\t{prob}")?;
+            }
+        }
+        Ok(w.flush()?)
+    }
 }
 
 pub struct Model {
@@ -211,12 +588,55 @@ impl Model {
     pub const CONFIDENCE_FILE: &'static str = "confidenceThresholds";
 
     // Load confidence thresholds
+    // the format, tab separated or JSON, is detected from the first character
     pub fn load_confidence(conf_file_path: &Path, strict: bool) -> Result<LangScores> {
-        let mut confidence = LangScores::new();
         let confidence_file = fs::read_to_string(conf_file_path)
             .with_context(|| "Could not open confidenceThreshold file")?;
-        let mut loaded_langs = LangBitmap::new();
+        Self::parse_confidence(&confidence_file, strict)
+    }
+
+    /// Load confidence thresholds from a JSON file with language codes as keys
+    pub fn load_confidence_json(conf_file_path: &Path, strict: bool) -> Result<LangScores> {
+        let confidence_file = fs::read_to_string(conf_file_path).with_context(|| {
+            format!(
+                "Could not open confidence thresholds file '{}'",
+                conf_file_path.display()
+            )
+        })?;
+        let entries = Self::parse_confidence_json(&confidence_file)?;
+        Self::check_confidence(entries, strict)
+    }
+
+    /// Save the non-zero confidence thresholds to a JSON file with language codes as keys
+    pub fn save_confidence_json(&self, path: &Path) -> Result<()> {
+        // Sorted by language code, so files can be compared
+        let thresholds: BTreeMap<_, _> = self
+            .to_confidence_map()
+            .into_iter()
+            .filter(|(lang, _)| !lang.is_special())
+            .map(|(lang, threshold)| (lang.to_string(), threshold))
+            .collect();
+        let file = File::create(path)
+            .with_context(|| format!("Could not create file '{}'", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, &thresholds)?;
+        writeln!(writer)?;
+        Ok(writer.flush()?)
+    }
 
+    // Parse the contents of a confidence thresholds file, tab separated or JSON
+    fn parse_confidence(confidence_file: &str, strict: bool) -> Result<LangScores> {
+        let entries = if confidence_file.trim_start().starts_with('{') {
+            Self::parse_confidence_json(confidence_file)?
+        } else {
+            Self::parse_confidence_tsv(confidence_file)?
+        };
+        Self::check_confidence(entries, strict)
+    }
+
+    // Parse the language and threshold of each line of a tab separated confidence file
+    fn parse_confidence_tsv(confidence_file: &str) -> Result<Vec<(Lang, f32)>> {
+        let mut entries = Vec::new();
         for (i, line) in confidence_file.trim_end().lines().enumerate() {
             let parts: Vec<&str> = line.trim_end().split('\t').collect();
             // Check that the number of fields are correct and the language exists
@@ -238,7 +658,32 @@ impl Model {
                     parts[1]
                 )
             })?;
+            entries.push((lang, prob));
+        }
+        Ok(entries)
+    }
+
+    // Parse a JSON confidence file with language codes as keys
+    fn parse_confidence_json(confidence_file: &str) -> Result<Vec<(Lang, f32)>> {
+        let thresholds: BTreeMap<String, f32> = serde_json::from_str(confidence_file)
+            .context("Could not parse JSON confidence file")?;
+        thresholds
+            .into_iter()
+            .map(|(code, prob)| {
+                let lang = Lang::from_str(&code).with_context(|| {
+                    format!("Loading confidence file, lang '{code}' does not exist")
+                })?;
+                Ok((lang, prob))
+            })
+            .collect()
+    }
+
+    // Build the thresholds and check that all the languages have one if strict
+    fn check_confidence(entries: Vec<(Lang, f32)>, strict: bool) -> Result<LangScores> {
+        let mut confidence = LangScores::new();
+        let mut loaded_langs = LangBitmap::new();
 
+        for (lang, prob) in entries {
             loaded_langs.set(&lang, true);
             confidence.insert(lang, prob);
         }
@@ -270,57 +715,349 @@ impl Model {
         from_text: bool,
         langs: Option<Vec<Lang>>,
     ) -> Result<Self> {
+        let orders: Vec<_> = OrderNgram::iter().collect();
+        Self::load_orders(modelpath, &orders, strict, from_text, langs)
+    }
+
+    /// Load the model from binary models and confidence thresholds file contents in memory
+    ///
+    /// This allows embedding the model in the binary with `include_bytes!`.
+    /// Orders that are not provided will be empty. Confidence thresholds are not loaded strictly.
+    pub fn from_bytes(ngrams: &[(&[u8], OrderNgram)], confidence: &[u8]) -> Result<Self> {
+        let mut inner = OrderNgram::iter().map(ModelNgram::new).collect::<Vec<_>>();
+        for (bytes, model_type) in ngrams {
+            let model = ModelNgram::from_reader(*bytes)
+                .with_context(|| format!("Could not load '{model_type}' model from bytes"))?;
+            if model.model_type != *model_type {
+                bail!(
+                    "Expected '{model_type}' model, but bytes contain a '{}' model",
+                    model.model_type
+                );
+            }
+            inner[*model_type as usize] = model;
+        }
+        let confidence = std::str::from_utf8(confidence)
+            .with_context(|| "Confidence thresholds are not valid UTF-8")?;
+
+        Ok(Self {
+            inner: inner.try_into().unwrap(),
+            confidence: Self::parse_confidence(confidence, false)?,
+        })
+    }
+
+    /// Non-blocking version of [`Self::load`] for binary models
+    ///
+    /// Files are read with `tokio::fs` and deserialized in blocking tasks,
+    /// so it needs to be called inside a Tokio runtime.
+    #[cfg(feature = "async")]
+    pub async fn load_async(modelpath: &Path, strict: bool) -> Result<Self> {
         debug!("Loading model from '{}", modelpath.display());
-        // Run a separated thread to load each model
-        let mut handles: Vec<thread::JoinHandle<_>> = Vec::new();
+        let mut handles = Vec::with_capacity(OrderNgram::COUNT);
         for model_type in OrderNgram::iter() {
-            let type_repr = model_type.to_string();
+            let filename = modelpath.join(format!("{model_type}.bin"));
+            handles.push(tokio::spawn(async move {
+                let content = tokio::fs::read(&filename).await.with_context(|| {
+                    format!("Could not open model file '{}'", filename.display())
+                })?;
+                let model =
+                    tokio::task::spawn_blocking(move || ModelNgram::from_reader(&content[..]))
+                        .await??;
+                // check model type is correct
+                assert!(model.model_type == model_type);
+                Ok::<ModelNgram, anyhow::Error>(model)
+            }));
+        }
+        let confidence_file = tokio::fs::read_to_string(modelpath.join(Self::CONFIDENCE_FILE))
+            .await
+            .with_context(|| "Could not open confidenceThreshold file")?;
 
-            if from_text || langs.is_some() {
-                // Load model from text
-                let modelpath_copy = PathBuf::from(modelpath);
-                let langs_copy = langs.clone();
-                handles.push(thread::spawn(move || {
-                    let model = ModelNgram::from_text(&modelpath_copy, model_type, langs_copy)?;
-                    Ok(model)
-                }));
-            } else {
-                // Load model binary
-                let filename = modelpath.join(format!("{type_repr}.bin"));
-                // If a model binary does not exist, fail early
+        let mut inner = Vec::with_capacity(OrderNgram::COUNT);
+        for handle in handles {
+            inner.push(handle.await??);
+        }
+
+        Ok(Self {
+            inner: inner.try_into().unwrap(),
+            confidence: Self::parse_confidence(&confidence_file, strict)?,
+        })
+    }
+
+    /// Load only some of the ngram orders, the rest of them will be empty
+    ///
+    /// Model is loaded from text if relevant languages are requested, from binary otherwise.
+    pub fn load_partial(
+        modelpath: &Path,
+        orders: &[OrderNgram],
+        strict: bool,
+        langs: Option<Vec<Lang>>,
+    ) -> Result<Self> {
+        Self::load_orders(modelpath, orders, strict, false, langs)
+    }
+
+    fn load_orders(
+        modelpath: &Path,
+        orders: &[OrderNgram],
+        strict: bool,
+        from_text: bool,
+        langs: Option<Vec<Lang>>,
+    ) -> Result<Self> {
+        debug!("Loading model from '{}", modelpath.display());
+        let from_text = from_text || langs.is_some();
+        // If a model binary does not exist, fail early
+        if !from_text {
+            for model_type in orders {
+                let filename = modelpath.join(format!("{model_type}.bin"));
                 if !filename.exists() {
                     let message = format!("Model file '{}' could not be found", filename.display());
-                    for h in handles {
-                        //TODO figure out how to propagate this
-                        let _ = h.join().unwrap()?;
-                    }
                     return Err(io::Error::new(io::ErrorKind::NotFound, message).into());
                 }
-                handles.push(thread::spawn(move || {
-                    let model = ModelNgram::from_bin(&filename)?;
-                    // check model type is correct
-                    assert!(model.model_type == model_type);
-                    Ok::<ModelNgram, anyhow::Error>(model)
-                }));
             }
         }
-        let confidence_scores =
-            Self::load_confidence(&modelpath.join(Self::CONFIDENCE_FILE), strict)?;
+
+        // Load each model in the rayon thread pool, so the global thread count is respected
+        // orders that were not requested are empty
+        let model_types: Vec<OrderNgram> = OrderNgram::iter().collect();
+        let (inner, confidence_scores) = rayon::join(
+            || {
+                model_types
+                    .into_par_iter()
+                    .map(|model_type| {
+                        if !orders.contains(&model_type) {
+                            debug!("{model_type}: not requested, leaving it empty");
+                            Ok(ModelNgram::new(model_type))
+                        } else if from_text {
+                            ModelNgram::from_text(modelpath, model_type, langs.clone())
+                        } else {
+                            let filename = modelpath.join(format!("{model_type}.bin"));
+                            let model = ModelNgram::from_bin(&filename)?;
+                            // check model type is correct
+                            assert!(model.model_type == model_type);
+                            Ok(model)
+                        }
+                    })
+                    .collect::<Result<Vec<_>>>()
+            },
+            || Self::load_confidence(&modelpath.join(Self::CONFIDENCE_FILE), strict),
+        );
+        let inner = inner?;
+        let confidence_scores = confidence_scores?;
 
         Ok(Self {
-            // remove first position because after removal, the vec is reindexed
-            inner: [
-                handles.remove(0).join().unwrap()?,
-                handles.remove(0).join().unwrap()?,
-                handles.remove(0).join().unwrap()?,
-                handles.remove(0).join().unwrap()?,
-                handles.remove(0).join().unwrap()?,
-                handles.remove(0).join().unwrap()?,
-                handles.remove(0).join().unwrap()?,
-            ],
+            inner: inner.try_into().unwrap(),
             confidence: confidence_scores,
         })
     }
+
+    /// Save the model in the plain text format, one file per language and ngram order
+    ///
+    /// Original frequencies are lost during binarization, so they are replaced by
+    /// frequencies that produce the same probabilities when loading the text model.
+    /// The languagelist and confidence thresholds files are also written.
+    pub fn save_text(&self, output_dir: &Path) -> Result<()> {
+        // Total frequency of each language, large enough to keep the precision of probabilities
+        const TOTAL: f64 = 1e12;
+
+        let mut langs: Vec<_> = self.languages().into_iter().collect();
+        langs.sort_by_key(|lang| *lang as usize);
+
+        for model in &self.inner {
+            let type_repr = model.model_type.to_string();
+            let mut lang_entries: HashMap<Lang, Vec<(&str, u64)>> = HashMap::new();
+            for (gram, probs) in model.iter() {
+                for (lang, prob) in probs {
+                    let amount = (10_f64.powf(-*prob as f64) * TOTAL).round() as u64;
+                    lang_entries.entry(*lang).or_default().push((gram, amount));
+                }
+            }
+
+            for lang in &langs {
+                let mut entries = lang_entries.remove(lang).unwrap_or_default();
+                // Text models are sorted by frequency, loading stops at the first infrequent gram
+                entries.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+                let total: u64 = entries.iter().map(|(_, amount)| amount).sum();
+
+                let lang_repr = lang.to_string().to_lowercase();
+                let path = output_dir.join(format!("{lang_repr}.{type_repr}.model"));
+                let file = File::create(&path)
+                    .with_context(|| format!("Could not create file '{}'", path.display()))?;
+                let mut writer = BufWriter::new(file);
+                writeln!(writer, "{total}")?;
+                for (gram, amount) in entries {
+                    writeln!(writer, "{gram}\t{amount}")?;
+                }
+                writer.flush()?;
+            }
+        }
+
+        let mut languagelist = BufWriter::new(File::create(output_dir.join("languagelist"))?);
+        let mut confidence = BufWriter::new(File::create(output_dir.join(Self::CONFIDENCE_FILE))?);
+        for lang in &langs {
+            writeln!(languagelist, "{}", lang.to_string().to_lowercase())?;
+        }
+        for lang in Lang::iter() {
+            let threshold = self.confidence.get(lang);
+            if !lang.is_special() && threshold != 0.0 {
+                writeln!(confidence, "{lang}\t{threshold}")?;
+            }
+        }
+        languagelist.flush()?;
+        confidence.flush()?;
+
+        info!("Saved text model at '{}'", output_dir.display());
+        Ok(())
+    }
+
+    /// Languages that have at least one entry in any of the ngram models
+    pub fn languages(&self) -> HashSet<Lang> {
+        self.inner
+            .iter()
+            .flat_map(|model| model.dic.values())
+            .flatten()
+            .map(|(lang, _)| *lang)
+            .collect()
+    }
+
+    /// Confidence thresholds of the languages that have one, that is, non-zero
+    pub fn to_confidence_map(&self) -> HashMap<Lang, f32> {
+        self.confidence
+            .to_map()
+            .into_iter()
+            .filter(|(_, threshold)| *threshold != 0.0)
+            .collect()
+    }
+
+    /// Create a new model restricted to a set of languages
+    pub fn subset(&self, langs: &[Lang]) -> Result<Model> {
+        if langs.is_empty() {
+            bail!("Cannot create a model subset without languages");
+        }
+
+        // Thresholds are only for macrolangs, so keep the collapsed ones
+        let mut confidence = LangScores::new();
+        for lang in langs {
+            let lang_col = lang.collapse();
+            confidence.insert(lang_col, self.confidence.get(lang_col));
+        }
+
+        Ok(Model {
+            inner: self.inner.each_ref().map(|model| model.filter_langs(langs)),
+            confidence,
+        })
+    }
+
+    /// Create a new model interpolating the probabilities of this model with another one,
+    /// for example a domain specific model, that has `weight` importance.
+    ///
+    /// See [`ModelNgram::interpolate`]. Confidence thresholds of this model are kept.
+    pub fn interpolate(&self, other: &Model, weight: f32) -> Result<Model> {
+        let inner: Vec<_> = self
+            .inner
+            .iter()
+            .zip(&other.inner)
+            .map(|(model, other_model)| model.interpolate(other_model, weight))
+            .collect::<Result<_>>()?;
+
+        Ok(Model {
+            inner: inner
+                .try_into()
+                .map_err(|_| anyhow!("Interpolated model has missing orders"))?,
+            confidence: self.confidence.clone(),
+        })
+    }
+
+    /// Check the internal consistency of the model
+    ///
+    /// Returns the list of non-fatal issues found, or an error if the model is unusable.
+    pub fn verify(&self) -> Result<Vec<String>> {
+        let mut warnings = Vec::new();
+        let mut langs = LangBitmap::new();
+
+        for (model, order) in self.inner.iter().zip(OrderNgram::iter()) {
+            warnings.extend(model.verify(order, &mut langs)?);
+        }
+        warnings.extend(Self::verify_confidence(&langs, &self.confidence));
+
+        Ok(warnings)
+    }
+
+    // Check that all the languages with entries have a confidence threshold
+    fn verify_confidence(langs: &LangBitmap, confidence: &LangScores) -> Vec<String> {
+        // Thresholds are only for macrolangs, so collapse
+        let mut collapsed = LangBitmap::new();
+        for lang in langs.iter_set() {
+            collapsed.set(&lang.collapse(), true);
+        }
+        collapsed
+            .iter_set()
+            .filter(|lang| !lang.is_special() && confidence.get(*lang) == 0.0)
+            .map(|lang| format!("Language '{lang}' does not have a confidence threshold"))
+            .collect()
+    }
+
+    /// Compute entry statistics of the model
+    pub fn stats(&self) -> ModelStats {
+        let mut order_entries = Vec::with_capacity(OrderNgram::COUNT);
+        let mut lang_entries = HashMap::new();
+        let mut heap_size = 0;
+        for model in &self.inner {
+            order_entries.push((model.model_type, model.dic.len()));
+            // Approximate the hash table size by its capacity plus the heap memory of each entry
+            heap_size += model.dic.capacity() * mem::size_of::<(String, Vec<(Lang, f32)>)>();
+            for (gram, probs) in &model.dic {
+                heap_size += gram.capacity() + probs.capacity() * mem::size_of::<(Lang, f32)>();
+                for (lang, _) in probs {
+                    *lang_entries.entry(*lang).or_insert(0) += 1;
+                }
+            }
+        }
+
+        ModelStats {
+            vocab_size: order_entries.iter().map(|(_, n)| n).sum(),
+            order_entries,
+            lang_entries,
+            heap_size,
+        }
+    }
+}
+
+/// Entry statistics of a [`Model`]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ModelStats {
+    /// Number of grams in each ngram order
+    pub order_entries: Vec<(OrderNgram, usize)>,
+    /// Number of grams that have a probability for each language
+    pub lang_entries: HashMap<Lang, usize>,
+    /// Total number of grams across all ngram orders
+    pub vocab_size: usize,
+    /// Approximate size of the model in memory, in bytes
+    pub heap_size: usize,
+}
+
+impl fmt::Display for ModelStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Vocabulary size: {}", self.vocab_size)?;
+        writeln!(
+            f,
+            "Approximate memory size: {:.2} MiB",
+            self.heap_size as f64 / (1024.0 * 1024.0)
+        )?;
+        writeln!(f, "Entries per ngram order:")?;
+        for (order, entries) in &self.order_entries {
+            writeln!(f, "  {order}\t{entries}")?;
+        }
+        writeln!(
+            f,
+            "Entries per language ({} languages):",
+            self.lang_entries.len()
+        )?;
+        let mut langs: Vec<_> = self.lang_entries.iter().collect();
+        langs.sort_by_key(|(lang, _)| **lang as usize);
+        for (lang, entries) in langs {
+            writeln!(f, "  {lang}\t{entries}")?;
+        }
+        Ok(())
+    }
 }
 
 // to avoid calling inner value
@@ -332,34 +1069,131 @@ impl Index<usize> for Model {
     }
 }
 
+/// Access to the ngram orders and confidence thresholds of a model,
+/// regardless of how they are loaded
+pub trait LanguageModel: Index<usize, Output = ModelNgram> + Send + Sync {
+    fn confidence(&self) -> &LangScores;
+}
+
+impl LanguageModel for Model {
+    fn confidence(&self) -> &LangScores {
+        &self.confidence
+    }
+}
+
+/// Model whose ngram orders are kept in memory from the first time they are accessed
+///
+/// Useful when only some of the orders are going to be used,
+/// as the rest are checked when loading but not kept in memory.
+pub struct LazyModel {
+    modelpath: PathBuf,
+    inner: [OnceLock<ModelNgram>; OrderNgram::COUNT],
+    pub confidence: LangScores,
+}
+
+impl LazyModel {
+    /// Load confidence thresholds and check that all the binary models can be read
+    pub fn load(modelpath: &Path, strict: bool) -> Result<Self> {
+        for model_type in OrderNgram::iter() {
+            let filename = modelpath.join(format!("{model_type}.bin"));
+            if !filename.exists() {
+                let message = format!("Model file '{}' could not be found", filename.display());
+                return Err(io::Error::new(io::ErrorKind::NotFound, message).into());
+            }
+            // Decode every order, one at a time, so accessing them later does not fail
+            ModelNgram::from_bin(&filename)?;
+        }
+        Ok(Self {
+            modelpath: modelpath.to_path_buf(),
+            inner: std::array::from_fn(|_| OnceLock::new()),
+            confidence: Model::load_confidence(&modelpath.join(Model::CONFIDENCE_FILE), strict)?,
+        })
+    }
+
+    /// Whether an order has already been loaded
+    pub fn is_loaded(&self, model_type: OrderNgram) -> bool {
+        self.inner[model_type as usize].get().is_some()
+    }
+
+    /// Obtain an order, loading it if it has not been loaded yet
+    pub fn get(&self, model_type: OrderNgram) -> Result<&ModelNgram> {
+        let cell = &self.inner[model_type as usize];
+        if let Some(model) = cell.get() {
+            return Ok(model);
+        }
+        let filename = self.modelpath.join(format!("{model_type}.bin"));
+        debug!("Lazy loading '{}'", filename.display());
+        let model = ModelNgram::from_bin(&filename)?;
+        // Another thread may have loaded it in the meantime, keep the first one
+        Ok(cell.get_or_init(|| model))
+    }
+}
+
+impl Index<usize> for LazyModel {
+    type Output = ModelNgram;
+
+    /// Load the order if it has not been loaded yet.
+    ///
+    /// Orders are checked in [`LazyModel::load`], so this only panics if the model files
+    /// changed afterwards. Use [`LazyModel::get`] to handle the error.
+    fn index(&self, num: usize) -> &Self::Output {
+        let model_type = OrderNgram::iter().nth(num).unwrap();
+        self.get(model_type)
+            .unwrap_or_else(|e| panic!("Could not load '{model_type}' model: {e:#}"))
+    }
+}
+
+impl LanguageModel for LazyModel {
+    fn confidence(&self) -> &LangScores {
+        &self.confidence
+    }
+}
+
 /// Binarize models and save in a path
-pub fn binarize(save_path: &Path, model_path: &Path, strict: bool) -> Result<()> {
+///
+/// If `compress` is enabled, binary files are compressed with zstd.
+pub fn binarize(save_path: &Path, model_path: &Path, strict: bool, compress: bool) -> Result<()> {
+    // Check conf file is ok by loading it
+    let conf_file_in = model_path.join(Model::CONFIDENCE_FILE);
+    let confidence = Model::load_confidence(&conf_file_in, strict)?;
+
     let orders: Vec<_> = OrderNgram::iter().collect();
 
     let results: Vec<Result<_>> = orders
         .par_iter()
         .panic_fuse()
-        .map(|model_type| -> Result<()> {
+        .map(|model_type| -> Result<LangBitmap> {
             let type_repr = model_type.to_string();
             info!("{type_repr}: loading text model");
             let model = ModelNgram::from_text(&model_path, model_type.clone(), None)?;
+            // Check each order before saving it, keeping only its languages
+            let mut langs = LangBitmap::new();
+            for warning in model.verify(*model_type, &mut langs)? {
+                warn!("{warning}");
+            }
             let size = model.dic.len();
             let filename = save_path.join(format!("{type_repr}.bin"));
             info!("{type_repr}: saving binarized model with {size} entries");
-            model.save(Path::new(&filename))
+            if compress {
+                model.save_compressed(Path::new(&filename))?;
+            } else {
+                model.save(Path::new(&filename))?;
+            }
+            Ok(langs)
         })
         .collect();
 
     // If there is one error, propagate
+    let mut langs = LangBitmap::new();
     for r in results {
-        let _ = r?;
+        langs = &langs | &r?;
+    }
+    for warning in Model::verify_confidence(&langs, &confidence) {
+        warn!("{warning}");
     }
 
     info!("Copying confidence thresholds file");
-    let conf_file_in = model_path.join(Model::CONFIDENCE_FILE);
     let conf_file_out = save_path.join(Model::CONFIDENCE_FILE);
-    // Check conf file is ok by loading it
-    let _ = Model::load_confidence(&conf_file_in, strict)?;
     fs::copy(conf_file_in, conf_file_out)?;
 
     info!("Saved models at '{}'", save_path.display());
@@ -377,7 +1211,7 @@ mod tests {
     fn test_langs() {
         let tempf = NamedTempFile::new().unwrap();
         let temppath = tempf.into_temp_path();
-        let modelpath = Path::new("LanguageModels");
+        let modelpath = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../LanguageModels"));
 
         let model = ModelNgram::from_text(&modelpath, OrderNgram::Quingram, None).unwrap();
         // let path = Path::new("gramdict.ser");
@@ -416,4 +1250,241 @@ mod tests {
         }
         assert_eq!(&probs, &expected);
     }
+
+    #[test]
+    fn test_merge() {
+        let mut a = ModelNgram {
+            dic: HashMap::default(),
+            model_type: OrderNgram::Word,
+        };
+        a.dic
+            .insert("hola".into(), vec![(Lang::spa, 2.0), (Lang::cat, 4.0)]);
+        a.dic.insert("adeu".into(), vec![(Lang::cat, 3.0)]);
+        let mut b = ModelNgram {
+            dic: HashMap::default(),
+            model_type: OrderNgram::Word,
+        };
+        b.dic
+            .insert("hola".into(), vec![(Lang::spa, 3.0), (Lang::glg, 5.0)]);
+        b.dic.insert("adios".into(), vec![(Lang::spa, 3.5)]);
+
+        let merged = a.merge(b).unwrap();
+        assert_eq!(merged.vocab_size(), 3);
+        assert_eq!(
+            merged.dic["hola"],
+            [(Lang::spa, 2.5), (Lang::cat, 4.0), (Lang::glg, 5.0)]
+        );
+        assert_eq!(merged.dic["adios"], [(Lang::spa, 3.5)]);
+
+        let other = ModelNgram {
+            dic: HashMap::default(),
+            model_type: OrderNgram::Bigram,
+        };
+        assert!(merged.merge(other).is_err());
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let mut base = ModelNgram::new(OrderNgram::Word);
+        base.dic
+            .insert("hola".into(), vec![(Lang::spa, 1.0), (Lang::cat, 2.0)]);
+        base.dic.insert("adeu".into(), vec![(Lang::cat, 3.0)]);
+        let mut domain = ModelNgram::new(OrderNgram::Word);
+        domain
+            .dic
+            .insert("hola".into(), vec![(Lang::spa, 2.0), (Lang::glg, 4.0)]);
+        domain.dic.insert("adios".into(), vec![(Lang::spa, 3.5)]);
+
+        let interpolated = base.interpolate(&domain, 0.5).unwrap();
+        assert_eq!(interpolated.vocab_size(), 3);
+        let expected = -(0.5 * 0.1_f64 + 0.5 * 0.01).log10() as f32;
+        assert_eq!(
+            interpolated.dic["hola"],
+            [(Lang::spa, expected), (Lang::cat, 2.0), (Lang::glg, 4.0)]
+        );
+        assert_eq!(interpolated.dic["adeu"], [(Lang::cat, 3.0)]);
+        assert_eq!(interpolated.dic["adios"], [(Lang::spa, 3.5)]);
+
+        assert_eq!(
+            base.interpolate(&domain, 0.0).unwrap().dic["hola"][0].1,
+            1.0
+        );
+        assert!(base.interpolate(&domain, 1.5).is_err());
+        assert!(base
+            .interpolate(&ModelNgram::new(OrderNgram::Bigram), 0.5)
+            .is_err());
+    }
+
+    #[test]
+    fn test_from_arpa() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cat.arpa");
+        fs::write(
+            &path,
+            "\\data\\\nngram 1=5\nngram 2=3\n\n\\1-grams:\n-99\t<s>\t-0.5\n-1\t</s>\n\
+             -0.5\thola\t-0.3\n-1.5\tadeu\n-2\t<unk>\n\n\\2-grams:\n-0.25\t<s> h\n\
+             -0.75\th o\n-1.25\tl a\n\n\\end\\\n",
+        )
+        .unwrap();
+
+        let words = ModelNgram::from_arpa(&path, Lang::cat, OrderNgram::Word).unwrap();
+        assert_eq!(words.model_type, OrderNgram::Word);
+        assert_eq!(words.vocab_size(), 2);
+        assert_eq!(words.dic["hola"], [(Lang::cat, 0.5)]);
+        assert_eq!(words.dic["adeu"], [(Lang::cat, 1.5)]);
+
+        let bigrams = ModelNgram::from_arpa(&path, Lang::cat, OrderNgram::Bigram).unwrap();
+        assert_eq!(bigrams.vocab_size(), 3);
+        assert_eq!(bigrams.dic[" h"], [(Lang::cat, 0.25)]);
+        assert_eq!(bigrams.dic["ho"], [(Lang::cat, 0.75)]);
+
+        assert!(ModelNgram::from_arpa(&path, Lang::cat, OrderNgram::Trigram).is_err());
+    }
+
+    #[test]
+    fn test_lazy_model_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(Model::CONFIDENCE_FILE), "").unwrap();
+        for order in OrderNgram::iter() {
+            ModelNgram::new(order)
+                .save(&dir.path().join(format!("{order}.bin")))
+                .unwrap();
+        }
+        let model = LazyModel::load(dir.path(), false).unwrap();
+
+        // Valid header but corrupt contents fail when the order is loaded
+        let mut corrupt = ModelNgram::FORMAT_MAGIC.to_vec();
+        corrupt.extend(ModelNgram::FORMAT_VERSION.to_le_bytes());
+        corrupt.extend([0xff; 3]);
+        fs::write(dir.path().join("bigram.bin"), &corrupt).unwrap();
+        assert!(model.get(OrderNgram::Bigram).is_err());
+        assert!(!model.is_loaded(OrderNgram::Bigram));
+        assert_eq!(model.get(OrderNgram::Word).unwrap().vocab_size(), 0);
+
+        // Corrupt contents and invalid headers fail when loading
+        assert!(LazyModel::load(dir.path(), false).is_err());
+        fs::write(dir.path().join("bigram.bin"), "not a model").unwrap();
+        assert!(LazyModel::load(dir.path(), false).is_err());
+    }
+
+    #[test]
+    fn test_to_tsv_writer() {
+        let mut model = ModelNgram::new(OrderNgram::Word);
+        model
+            .dic
+            .insert("hola".into(), vec![(Lang::spa, 2.0), (Lang::cat, 4.5)]);
+        model.dic.insert("adeu".into(), vec![(Lang::cat, 3.25)]);
+
+        let mut output = Vec::new();
+        model.to_tsv_writer(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "adeu\tcat\t3.25\nhola\tcat\t4.5\nhola\tspa\t2\n"
+        );
+    }
+
+    #[test]
+    fn test_confidence_json() {
+        let modelpath = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../LanguageModels"));
+        let model = Model::load(modelpath, false, true, Some(vec![Lang::cat, Lang::spa])).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("confidence.json");
+        model.save_confidence_json(&path).unwrap();
+
+        let json = Model::load_confidence_json(&path, true).unwrap();
+        // Both formats are accepted by the generic loader
+        let detected = Model::load_confidence(&path, true).unwrap();
+        let tsv = Model::load_confidence(&modelpath.join(Model::CONFIDENCE_FILE), true).unwrap();
+        for lang in Lang::iter() {
+            assert_eq!(json.get(lang), tsv.get(lang), "{lang}");
+            assert_eq!(detected.get(lang), tsv.get(lang), "{lang}");
+        }
+
+        fs::write(&path, r#"{"cat": 0.5}"#).unwrap();
+        assert!(Model::load_confidence_json(&path, true).is_err());
+        assert_eq!(
+            Model::load_confidence_json(&path, false)
+                .unwrap()
+                .get(Lang::cat),
+            0.5
+        );
+        fs::write(&path, r#"{"xyz": 0.5}"#).unwrap();
+        assert!(Model::load_confidence(&path, false).is_err());
+    }
+
+    #[test]
+    fn test_save_text() {
+        let modelpath = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../LanguageModels"));
+        let langs = vec![Lang::cat, Lang::spa];
+        let model = Model::load(modelpath, false, true, Some(langs.clone())).unwrap();
+
+        let tempdir = tempfile::tempdir().unwrap();
+        model.save_text(tempdir.path()).unwrap();
+        let reloaded = Model::load(tempdir.path(), true, true, None).unwrap();
+
+        assert_eq!(reloaded.languages(), model.languages());
+        for order in OrderNgram::iter() {
+            let (expected, loaded) = (&model[order as usize], &reloaded[order as usize]);
+            assert_eq!(expected.vocab_size(), loaded.vocab_size());
+            for (gram, probs) in expected.iter() {
+                for ((lang, prob), (loaded_lang, loaded_prob)) in
+                    probs.iter().zip(&loaded.dic[gram])
+                {
+                    assert_eq!(lang, loaded_lang);
+                    assert!(
+                        (prob - loaded_prob).abs() < 1e-4,
+                        "{gram} {prob} {loaded_prob}"
+                    );
+                }
+            }
+        }
+        assert_eq!(
+            reloaded.confidence.get(Lang::spa),
+            model.confidence.get(Lang::spa)
+        );
+        let thresholds = model.to_confidence_map();
+        assert_eq!(thresholds[&Lang::spa], model.confidence.get(Lang::spa));
+        assert!(!thresholds.contains_key(&Lang::und));
+        assert!(thresholds.values().all(|threshold| *threshold != 0.0));
+    }
+
+    #[test]
+    fn test_binarize() {
+        let modelpath = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../LanguageModels"));
+        let model = Model::load(modelpath, false, true, Some(vec![Lang::cat, Lang::spa])).unwrap();
+        let text_dir = tempfile::tempdir().unwrap();
+        model.save_text(text_dir.path()).unwrap();
+
+        let bin_dir = tempfile::tempdir().unwrap();
+        binarize(bin_dir.path(), text_dir.path(), false, false).unwrap();
+        let text = Model::load(text_dir.path(), false, true, None).unwrap();
+        let bin = Model::load(bin_dir.path(), false, false, None).unwrap();
+        for order in OrderNgram::iter() {
+            assert_eq!(bin[order as usize], text[order as usize], "{order}");
+        }
+        assert_eq!(
+            bin.confidence.get(Lang::spa),
+            text.confidence.get(Lang::spa)
+        );
+    }
+
+    #[test]
+    fn test_read_model_bom() {
+        let text = "10\nhola\t6\nadeu\t4\n";
+        let plain_dir = tempfile::tempdir().unwrap();
+        fs::write(plain_dir.path().join("cat.word.model"), text).unwrap();
+        let bom_dir = tempfile::tempdir().unwrap();
+        fs::write(
+            bom_dir.path().join("cat.word.model"),
+            format!("\u{FEFF}{text}"),
+        )
+        .unwrap();
+
+        let expected =
+            ModelNgram::from_text_langs(plain_dir.path(), OrderNgram::Word, vec![Lang::cat])
+                .unwrap();
+        let model =
+            ModelNgram::from_text_langs(bom_dir.path(), OrderNgram::Word, vec![Lang::cat]).unwrap();
+        assert_eq!(model, expected);
+    }
 }
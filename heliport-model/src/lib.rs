@@ -1,5 +1,7 @@
 pub mod lang;
 pub mod languagemodel;
 
-pub use crate::lang::{Lang, LangBitmap, LangScores};
-pub use crate::languagemodel::{binarize, Model, ModelNgram, OrderNgram};
+pub use crate::lang::{Lang, LangBitmap, LangFamily, LangScores};
+pub use crate::languagemodel::{
+    binarize, LanguageModel, LazyModel, Model, ModelNgram, ModelStats, OrderNgram,
+};
@@ -0,0 +1,84 @@
+//! WebAssembly bindings for heliport.
+//!
+//! The model is not read from disk, the contents of the binarized model files
+//! have to be provided, for example obtained with `fetch`:
+//! ```js
+//! const models = {};
+//! for (const order of ["word", "unigram", "bigram", "trigram", "quadgram", "quingram", "hexagram"]) {
+//!     const response = await fetch(`models/${order}.bin`);
+//!     models[order] = new Uint8Array(await response.arrayBuffer());
+//! }
+//! const confidence = new Uint8Array(await (await fetch("models/confidenceThresholds")).arrayBuffer());
+//! const identifier = new Identifier(models, confidence);
+//! identifier.identify("L'aigua clara");
+//! ```
+use std::sync::Arc;
+
+use js_sys::{Array, Object, Reflect, Uint8Array};
+use strum::IntoEnumIterator;
+use wasm_bindgen::prelude::*;
+
+use heliport::identifier;
+use heliport_model::{Model, OrderNgram};
+
+#[wasm_bindgen]
+pub struct Identifier {
+    inner: identifier::Identifier,
+}
+
+#[wasm_bindgen]
+impl Identifier {
+    /// Create an identifier from the contents of the binarized model files.
+    ///
+    /// `models` is an object with the name of each ngram order as key
+    /// and the contents of its `.bin` file as `Uint8Array`.
+    /// Orders that are not present will be empty.
+    /// `confidence` is the contents of the `confidenceThresholds` file.
+    #[wasm_bindgen(constructor)]
+    pub fn new(models: &Object, confidence: &[u8]) -> Result<Identifier, JsError> {
+        let mut contents = Vec::new();
+        for order in OrderNgram::iter() {
+            let value = Reflect::get(models, &JsValue::from_str(&order.to_string()))
+                .map_err(|_| JsError::new(&format!("Could not read '{order}' model")))?;
+            if value.is_undefined() {
+                continue;
+            }
+            contents.push((Uint8Array::new(&value).to_vec(), order));
+        }
+        let ngrams: Vec<_> = contents
+            .iter()
+            .map(|(bytes, order)| (bytes.as_slice(), *order))
+            .collect();
+        let model =
+            Model::from_bytes(&ngrams, confidence).map_err(|e| JsError::new(&format!("{e:#}")))?;
+
+        Ok(Self {
+            inner: identifier::Identifier::new(Arc::new(model)),
+        })
+    }
+
+    /// Identify the language of a string.
+    ///
+    /// Predictions below the confidence threshold are labeled as 'und'.
+    pub fn identify(&mut self, text: &str) -> String {
+        self.inner.identify(text, false).0.to_string()
+    }
+
+    /// Identify the language of a string and return the prediction and its confidence.
+    pub fn identify_with_score(&mut self, text: &str) -> Array {
+        let (lang, score) = self.inner.identify(text, false);
+        Array::of2(
+            &JsValue::from_str(&lang.to_string()),
+            &JsValue::from_f64(score as f64),
+        )
+    }
+
+    /// Identify the top-k languages of a string.
+    pub fn identify_topk(&mut self, text: &str, k: usize) -> Vec<String> {
+        self.inner
+            .identify_topk(text, k)
+            .iter()
+            .map(|(lang, _)| lang.to_string())
+            .collect()
+    }
+}
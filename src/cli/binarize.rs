@@ -26,6 +26,8 @@ pub struct BinarizeCmd {
           short = 's',
           long)]
     not_strict: bool,
+    #[arg(short, long, help = "Compress binary files with zstd")]
+    compress: bool,
 }
 
 impl BinarizeCmd {
@@ -53,7 +55,7 @@ impl BinarizeCmd {
             exit(1);
         }
 
-        binarize(&save_path, &model_path, !self.not_strict).or_abort(1);
+        binarize(&save_path, &model_path, !self.not_strict, self.compress).or_abort(1);
         Ok(())
     }
 }
@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use clap::Args;
+use log::{debug, info};
+use rayon::prelude::*;
+
+use super::eval::{open_reader, read_labeled, Evaluation};
+use super::parse_langs;
+use crate::identifier::Identifier;
+use crate::utils::Abort;
+
+#[derive(Args, Clone, Debug)]
+pub struct CompareModelsCmd {
+    #[arg(
+        long,
+        help = "Model directory of the first model, binarized or plain text"
+    )]
+    model_a: PathBuf,
+    #[arg(
+        long,
+        help = "Model directory of the second model, binarized or plain text"
+    )]
+    model_b: PathBuf,
+    #[arg(
+        short,
+        long,
+        help = "Tab separated file with the texts and their gold labels, default: stdin"
+    )]
+    input: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Column index (starting at 0) containing the text"
+    )]
+    text_col: usize,
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Column index (starting at 0) containing the gold label"
+    )]
+    label_col: usize,
+
+    #[arg(
+        short = 'c',
+        long,
+        help = "Ignore confidence thresholds. Predictions under the thresholds will not be labeled as 'und'"
+    )]
+    ignore_confidence: bool,
+    #[arg(
+        short = 'n',
+        long,
+        help = "Do not be strict when loading confidence thresholds (do not fail if one language is missing)"
+    )]
+    not_strict: bool,
+    #[arg(
+        long,
+        short,
+        default_value_t = 4,
+        help = "Number of decimals precision when printing metrics"
+    )]
+    precision: usize,
+    #[arg(
+        long,
+        help = "Write the lines where both models disagree to a tab separated file, with the line number, gold label, both predictions and the text"
+    )]
+    disagreements: Option<PathBuf>,
+    #[arg(
+        long,
+        short = 'l',
+        value_delimiter = ',',
+        help = "Load only relevant languages in both models. Specify a comma-separated list of language codes. Needs plain text model directories"
+    )]
+    relevant_langs: Option<Vec<String>>,
+}
+
+impl CompareModelsCmd {
+    pub fn cli(self) -> Result<()> {
+        info!("Starting");
+        let now = Instant::now();
+
+        let mut relevant_langs = None;
+        if let Some(r) = &self.relevant_langs {
+            relevant_langs = Some(parse_langs(r).or_abort(1));
+        }
+        debug!("{:?}", self);
+
+        let reader = match &self.input {
+            Some(p) => open_reader(p).or_abort(1),
+            None => Box::new(io::stdin().lock()),
+        };
+        let (texts, gold) = read_labeled(reader, self.text_col, self.label_col).or_abort(1);
+        info!("Read {} labeled texts", texts.len());
+
+        // Identify all the texts with each model, one model loaded at a time
+        let mut preds = Vec::with_capacity(2);
+        for model_dir in [&self.model_a, &self.model_b] {
+            info!("Loading model '{}'", model_dir.display());
+            let identifier =
+                Identifier::load(model_dir, relevant_langs.clone(), Some(!self.not_strict))
+                    .or_abort(1);
+            preds.push(
+                identifier
+                    .par_identify_str(texts.par_iter().map(String::as_str), self.ignore_confidence),
+            );
+        }
+        let (preds_b, preds_a) = (preds.pop().unwrap(), preds.pop().unwrap());
+
+        let mut eval_a = Evaluation::default();
+        let mut eval_b = Evaluation::default();
+        let mut disagreements = Vec::new();
+        for (i, gold) in gold.into_iter().enumerate() {
+            let (pred_a, pred_b) = (preds_a[i].0, preds_b[i].0);
+            eval_a.add(gold, pred_a);
+            eval_b.add(gold, pred_b);
+            if pred_a != pred_b {
+                disagreements.push((i, gold, pred_a, pred_b));
+            }
+        }
+
+        let precision = self.precision;
+        let mut stdout = io::stdout().lock();
+        writeln!(stdout, "accuracy_a\t{:.precision$}", eval_a.accuracy())?;
+        writeln!(stdout, "accuracy_b\t{:.precision$}", eval_b.accuracy())?;
+        writeln!(stdout, "disagreements\t{}", disagreements.len())?;
+
+        // F1 changes of each language, improvements first
+        let mut changes: Vec<_> = eval_a
+            .labels()
+            .into_iter()
+            .chain(eval_b.labels())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .map(|lang| (lang, eval_a.metrics(lang).2, eval_b.metrics(lang).2))
+            .filter(|(_, f1_a, f1_b)| f1_a != f1_b)
+            .collect();
+        changes.sort_by(|a, b| {
+            (b.2 - b.1)
+                .total_cmp(&(a.2 - a.1))
+                .then_with(|| a.0.to_string().cmp(&b.0.to_string()))
+        });
+        writeln!(stdout)?;
+        writeln!(stdout, "lang\tf1_a\tf1_b\tdiff")?;
+        for (lang, f1_a, f1_b) in &changes {
+            writeln!(
+                stdout,
+                "{lang}\t{f1_a:.precision$}\t{f1_b:.precision$}\t{:+.precision$}",
+                f1_b - f1_a
+            )?;
+        }
+        writeln!(stdout)?;
+        let improved = changes.iter().filter(|(_, a, b)| b > a).count();
+        writeln!(stdout, "improved\t{improved}")?;
+        writeln!(stdout, "regressed\t{}", changes.len() - improved)?;
+
+        if let Some(p) = &self.disagreements {
+            let file = File::create(p)
+                .with_context(|| format!("Error opening output file {} for writing", p.display()))
+                .or_abort(1);
+            let mut writer = BufWriter::new(file);
+            for (i, gold, pred_a, pred_b) in disagreements {
+                writeln!(
+                    writer,
+                    "{}\t{gold}\t{pred_a}\t{pred_b}\t{}",
+                    i + 1,
+                    texts[i]
+                )?;
+            }
+        }
+
+        info!("Finished");
+        info!("Elapsed time: {:.2?}", now.elapsed());
+        Ok(())
+    }
+}
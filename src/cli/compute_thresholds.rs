@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+use std::time::Instant;
+
+use anyhow::Result;
+use clap::Args;
+use log::{debug, info};
+
+use super::eval::{open_reader, read_labeled};
+use super::{parse_langs, resolve_model_dir};
+use crate::identifier::Identifier;
+use crate::utils::Abort;
+
+#[derive(Args, Clone, Debug)]
+pub struct ComputeThresholdsCmd {
+    #[arg(long, help = "Tab separated file with the texts and their gold labels")]
+    labeled: PathBuf,
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Column index (starting at 0) containing the text"
+    )]
+    text_col: usize,
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Column index (starting at 0) containing the gold label"
+    )]
+    label_col: usize,
+    #[arg(
+        long,
+        default_value_t = 0.95,
+        help = "Recall each language has to keep with its threshold"
+    )]
+    target_recall: f32,
+    #[arg(short, long, help = "Output confidence thresholds file")]
+    output: PathBuf,
+    #[arg(
+        short = 'n',
+        long,
+        help = "Do not be strict when loading confidence thresholds (do not fail if one language is missing)"
+    )]
+    not_strict: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Model directory containing binarized model or plain text model. Default is Python module path or './LanguageModels' if relevant languages are requested"
+    )]
+    model_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        short = 'l',
+        value_delimiter = ',',
+        help = "Load only relevant languages. Specify a comma-separated list of language codes. Needs plain text model directory"
+    )]
+    relevant_langs: Option<Vec<String>>,
+}
+
+impl ComputeThresholdsCmd {
+    pub fn cli(self) -> Result<()> {
+        info!("Starting");
+        let now = Instant::now();
+
+        let mut relevant_langs = None;
+        if let Some(r) = &self.relevant_langs {
+            relevant_langs = Some(parse_langs(r).or_abort(1));
+        }
+        debug!("{:?}", self);
+        let model_dir = resolve_model_dir(&self.model_dir, relevant_langs.is_some())?;
+
+        let reader = open_reader(&self.labeled).or_abort(1);
+        let (texts, gold) = read_labeled(reader, self.text_col, self.label_col).or_abort(1);
+        info!("Read {} labeled texts", texts.len());
+        let labeled: Vec<_> = texts.into_iter().zip(gold).collect();
+
+        info!("Loading model");
+        let mut identifier =
+            Identifier::load(&model_dir, relevant_langs, Some(!self.not_strict)).or_abort(1);
+        // Languages without labeled data keep the thresholds of the model
+        identifier
+            .calibrate_recall(&labeled, self.target_recall)
+            .or_abort(1);
+        identifier.save_thresholds(&self.output).or_abort(1);
+
+        info!("Finished");
+        info!("Elapsed time: {:.2?}", now.elapsed());
+        Ok(())
+    }
+}
@@ -7,7 +7,7 @@ use clap::Args;
 use log::{error, info};
 use rayon::prelude::*;
 
-use crate::trainer::count_all_ngrams;
+use crate::trainer::{count_all_ngrams, generate_languagelist};
 use crate::utils::Abort;
 
 #[derive(Args, Clone)]
@@ -23,6 +23,24 @@ pub struct CreateModelCmd {
         help = "Truncate at top-k most frequent n-grams"
     )]
     topk: usize,
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Discard n-grams with frequency lower than this"
+    )]
+    min_frequency: u64,
+    #[arg(
+        long,
+        help = "Write the languagelist file with all the languages in the output directory after training"
+    )]
+    generate_languagelist: bool,
+    #[arg(
+        long,
+        help = "Skip the model files that are newer than their input file"
+    )]
+    incremental: bool,
+    #[arg(long, help = "Regenerate all the model files, even with --incremental")]
+    force: bool,
 }
 
 impl CreateModelCmd {
@@ -39,6 +57,17 @@ impl CreateModelCmd {
         }
 
         info!("Saving top {} most frequent n-grams", self.topk);
+        if self.min_frequency > 1 {
+            info!(
+                "Discarding n-grams with frequency lower than {}",
+                self.min_frequency
+            );
+        }
+
+        let incremental = self.incremental && !self.force;
+        if incremental {
+            info!("Skipping model files that are up to date");
+        }
 
         // Train each file/language in parallel
         // use panic_fuse to fail early if one of the jobs fail
@@ -46,11 +75,21 @@ impl CreateModelCmd {
             .into_par_iter()
             .panic_fuse()
             .for_each(|lang_file| {
-                count_all_ngrams(&lang_file, &self.output_dir, self.topk)
-                    .with_context(|| format!("Error with file '{}'", lang_file.display()))
-                    .or_abort(1);
+                count_all_ngrams(
+                    &lang_file,
+                    &self.output_dir,
+                    self.topk,
+                    self.min_frequency,
+                    incremental,
+                )
+                .with_context(|| format!("Error with file '{}'", lang_file.display()))
+                .or_abort(1);
             });
 
+        if self.generate_languagelist {
+            generate_languagelist(&self.output_dir, &self.output_dir).or_abort(1);
+        }
+
         info!("Finished");
         info!("Elapsed time: {:.2?}", now.elapsed());
         Ok(())
@@ -0,0 +1,214 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use log::{debug, info, warn};
+use rayon::prelude::*;
+use serde_json::json;
+
+use super::eval::{open_reader, EvalFormat, Evaluation};
+use crate::identifier::Identifier;
+use crate::trainer::{count_all_ngrams, generate_languagelist};
+use crate::utils::Abort;
+use heliport_model::{binarize, Lang, Model};
+
+#[derive(Args, Clone, Debug)]
+pub struct CrossValidateCmd {
+    #[arg(
+        long,
+        help = "Directory containing the '.train' files of each language"
+    )]
+    input_dir: PathBuf,
+    #[arg(
+        long,
+        default_value_t = 5,
+        value_parser = clap::value_parser!(u64).range(2..),
+        help = "Number of folds the lines of each training file are split into"
+    )]
+    folds: u64,
+    #[arg(
+        short = 'k',
+        long,
+        default_value_t = 10000,
+        help = "Truncate at top-k most frequent n-grams"
+    )]
+    topk: usize,
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Discard n-grams with frequency lower than this"
+    )]
+    min_frequency: u64,
+    #[arg(
+        long,
+        short,
+        default_value_t = 4,
+        help = "Number of decimals precision when printing metrics"
+    )]
+    precision: usize,
+    #[arg(short = 'F', long, value_enum, default_value_t = EvalFormat::Tsv, help = "Output format")]
+    output_format: EvalFormat,
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Omit the metrics of languages with less than N gold labels, also from the macro average"
+    )]
+    min_support: usize,
+}
+
+impl CrossValidateCmd {
+    // Write the training lines of a fold for each language to the train dir
+    // and return the held out lines with their labels
+    fn split_fold(
+        &self,
+        files: &[(Lang, PathBuf)],
+        fold: u64,
+        train_dir: &Path,
+    ) -> Result<(Vec<String>, Vec<Lang>)> {
+        let mut texts = Vec::new();
+        let mut labels = Vec::new();
+        for (lang, path) in files {
+            let train_path = train_dir.join(format!("{lang}.train"));
+            let mut writer = BufWriter::new(File::create(&train_path).with_context(|| {
+                format!(
+                    "Error opening output file {} for writing",
+                    train_path.display()
+                )
+            })?);
+            for (i, line) in open_reader(path)?.lines().enumerate() {
+                let line = line?;
+                if i as u64 % self.folds != fold {
+                    writeln!(writer, "{line}")?;
+                } else if !line.trim().is_empty() {
+                    texts.push(line);
+                    labels.push(*lang);
+                }
+            }
+            writer.flush()?;
+        }
+        Ok((texts, labels))
+    }
+
+    // Train and binarize a model in a temporary directory and evaluate it on the held out lines
+    fn run_fold(&self, files: &[(Lang, PathBuf)], fold: u64) -> Result<Evaluation> {
+        let tempdir = tempfile::tempdir().context("Could not create temporary directory")?;
+        let train_dir = tempdir.path().join("train");
+        let text_dir = tempdir.path().join("text");
+        let bin_dir = tempdir.path().join("bin");
+        for dir in [&train_dir, &text_dir, &bin_dir] {
+            fs::create_dir(dir)?;
+        }
+
+        let (texts, gold) = self.split_fold(files, fold, &train_dir)?;
+        files.par_iter().try_for_each(|(lang, _)| {
+            let train_path = train_dir.join(format!("{lang}.train"));
+            count_all_ngrams(&train_path, &text_dir, self.topk, self.min_frequency, false)
+        })?;
+        generate_languagelist(&text_dir, &text_dir)?;
+        // There are no confidence thresholds for the partial models
+        File::create(text_dir.join(Model::CONFIDENCE_FILE))?;
+        binarize(&bin_dir, &text_dir, false, false)?;
+
+        let identifier = Identifier::load(&bin_dir, None, Some(false))?;
+        let preds = identifier.par_identify_str(texts.par_iter().map(String::as_str), true);
+        let mut evaluation = Evaluation::default();
+        for (gold, (pred, _)) in gold.into_iter().zip(preds) {
+            evaluation.add(gold, pred);
+        }
+        Ok(evaluation)
+    }
+
+    pub fn cli(self) -> Result<()> {
+        info!("Starting");
+        let now = Instant::now();
+        debug!("{:?}", self);
+
+        // Training files named with the language code
+        let mut files = Vec::new();
+        let entries = fs::read_dir(&self.input_dir)
+            .with_context(|| format!("Could not read directory '{}'", self.input_dir.display()))
+            .or_abort(1);
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().map_or(true, |ext| ext != "train") {
+                continue;
+            }
+            let code = path.file_stem().unwrap_or_default().to_string_lossy();
+            match Lang::from_str(&code) {
+                Ok(lang) => files.push((lang, path)),
+                Err(_) => warn!(
+                    "Language code '{code}' does not exist, skipping '{}'",
+                    path.display()
+                ),
+            }
+        }
+        files.sort_by(|a, b| a.1.cmp(&b.1));
+        if files.is_empty() {
+            bail!("No '.train' files found in '{}'", self.input_dir.display());
+        }
+        info!(
+            "Cross-validating {} languages with {} folds",
+            files.len(),
+            self.folds
+        );
+
+        let mut evaluations = Vec::with_capacity(self.folds as usize);
+        for fold in 0..self.folds {
+            info!("Fold {}/{}", fold + 1, self.folds);
+            evaluations.push(
+                self.run_fold(&files, fold)
+                    .with_context(|| format!("Error in fold {}", fold + 1))
+                    .or_abort(1),
+            );
+        }
+        // Metrics of the predictions of all folds together
+        let mut total = Evaluation::default();
+        for evaluation in &evaluations {
+            total.merge(evaluation);
+        }
+        let average =
+            evaluations.iter().map(Evaluation::accuracy).sum::<f64>() / evaluations.len() as f64;
+
+        let precision = self.precision;
+        let mut stdout = io::stdout().lock();
+        match self.output_format {
+            EvalFormat::Tsv => {
+                writeln!(stdout, "fold\taccuracy\tmacro-f1\tmicro-f1\tsupport")?;
+                for (fold, evaluation) in evaluations.iter().enumerate() {
+                    writeln!(
+                        stdout,
+                        "{}\t{:.precision$}\t{:.precision$}\t{:.precision$}\t{}",
+                        fold + 1,
+                        evaluation.accuracy(),
+                        evaluation.macro_f1(self.min_support),
+                        evaluation.micro_f1(),
+                        evaluation.total
+                    )?;
+                }
+                writeln!(stdout, "average\t{average:.precision$}")?;
+                writeln!(stdout)?;
+                total.write_metrics(&mut stdout, precision, self.min_support)?;
+            }
+            EvalFormat::Json => {
+                let folds: Vec<_> = evaluations
+                    .iter()
+                    .map(|evaluation| evaluation.report(self.min_support))
+                    .collect();
+                let report = json!({
+                    "folds": folds,
+                    "average_accuracy": average,
+                    "all_folds": total.report(self.min_support),
+                });
+                writeln!(stdout, "{report}")?;
+            }
+        }
+
+        info!("Finished");
+        info!("Elapsed time: {:.2?}", now.elapsed());
+        Ok(())
+    }
+}
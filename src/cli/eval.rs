@@ -0,0 +1,403 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, ValueEnum};
+use log::{debug, info};
+use rayon::prelude::*;
+use serde_json::json;
+
+use super::{parse_langs, resolve_model_dir};
+use crate::identifier::Identifier;
+use crate::utils::Abort;
+use heliport_model::Lang;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub(super) enum EvalFormat {
+    /// Tab separated tables
+    Tsv,
+    /// JSON report
+    Json,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct EvalCmd {
+    #[arg(
+        short,
+        long,
+        help = "Tab separated file with the texts and their gold labels, default: stdin"
+    )]
+    input: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Column index (starting at 0) containing the text"
+    )]
+    text_col: usize,
+    #[arg(
+        long,
+        default_value_t = 1,
+        help = "Column index (starting at 0) containing the gold label"
+    )]
+    label_col: usize,
+
+    #[arg(
+        short = 'c',
+        long,
+        help = "Ignore confidence thresholds. Predictions under the thresholds will not be labeled as 'und'"
+    )]
+    ignore_confidence: bool,
+    #[arg(
+        short = 'n',
+        long,
+        help = "Do not be strict when loading confidence thresholds (do not fail if one language is missing)"
+    )]
+    not_strict: bool,
+    #[arg(
+        long,
+        short,
+        default_value_t = 4,
+        help = "Number of decimals precision when printing metrics"
+    )]
+    precision: usize,
+    #[arg(long, help = "Write the full confusion matrix to a tab separated file")]
+    confusion_matrix: Option<PathBuf>,
+    #[arg(long, help = "Print the N most frequently confused pairs of languages")]
+    top_confused: Option<usize>,
+    #[arg(short = 'F', long, value_enum, default_value_t = EvalFormat::Tsv, help = "Output format")]
+    output_format: EvalFormat,
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Omit the metrics of languages with less than N gold labels, also from the macro average"
+    )]
+    min_support: usize,
+
+    #[arg(
+        short,
+        long,
+        help = "Model directory containing binarized model or plain text model. Default is Python module path or './LanguageModels' if relevant languages are requested"
+    )]
+    model_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        short = 'l',
+        value_delimiter = ',',
+        help = "Load only relevant languages. Specify a comma-separated list of language codes. Needs plain text model directory"
+    )]
+    relevant_langs: Option<Vec<String>>,
+}
+
+fn ratio(a: usize, b: usize) -> f64 {
+    if b == 0 {
+        0.0
+    } else {
+        a as f64 / b as f64
+    }
+}
+
+// Counts of gold and predicted label pairs
+#[derive(Default)]
+pub(super) struct Evaluation {
+    confusion: HashMap<(Lang, Lang), usize>,
+    pub(super) total: usize,
+}
+
+impl Evaluation {
+    pub(super) fn add(&mut self, gold: Lang, pred: Lang) {
+        *self.confusion.entry((gold, pred)).or_insert(0) += 1;
+        self.total += 1;
+    }
+
+    fn correct(&self) -> usize {
+        self.confusion
+            .iter()
+            .filter(|((gold, pred), _)| gold == pred)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    // Every language appearing either as gold or predicted, sorted by code
+    pub(super) fn labels(&self) -> Vec<Lang> {
+        let mut labels: Vec<_> = self
+            .confusion
+            .keys()
+            .flat_map(|(gold, pred)| [*gold, *pred])
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        labels.sort_by_key(|l| l.to_string());
+        labels
+    }
+
+    // Return true positives, gold count and predicted count of a language
+    fn counts(&self, lang: Lang) -> (usize, usize, usize) {
+        let (mut tp, mut gold, mut pred) = (0, 0, 0);
+        for ((g, p), count) in &self.confusion {
+            if *g == lang && *p == lang {
+                tp += count;
+            }
+            if *g == lang {
+                gold += count;
+            }
+            if *p == lang {
+                pred += count;
+            }
+        }
+        (tp, gold, pred)
+    }
+
+    pub(super) fn accuracy(&self) -> f64 {
+        ratio(self.correct(), self.total)
+    }
+
+    // Return precision, recall, F1 and support of a language
+    pub(super) fn metrics(&self, lang: Lang) -> (f64, f64, f64, usize) {
+        let (tp, gold, pred) = self.counts(lang);
+        let p = ratio(tp, pred);
+        let r = ratio(tp, gold);
+        let f1 = if p + r == 0.0 {
+            0.0
+        } else {
+            2.0 * p * r / (p + r)
+        };
+        (p, r, f1, gold)
+    }
+
+    // Merge the counts of another evaluation
+    pub(super) fn merge(&mut self, other: &Evaluation) {
+        for (pair, count) in &other.confusion {
+            *self.confusion.entry(*pair).or_insert(0) += count;
+        }
+        self.total += other.total;
+    }
+
+    // Languages with enough gold labels to report their metrics
+    fn reported_labels(&self, min_support: usize) -> Vec<Lang> {
+        self.labels()
+            .into_iter()
+            .filter(|lang| self.counts(*lang).1 >= min_support)
+            .collect()
+    }
+
+    // Average F1 of the reported languages present in the gold labels
+    pub(super) fn macro_f1(&self, min_support: usize) -> f64 {
+        let f1s: Vec<_> = self
+            .reported_labels(min_support.max(1))
+            .into_iter()
+            .map(|lang| self.metrics(lang).2)
+            .collect();
+        f1s.iter().sum::<f64>() / f1s.len().max(1) as f64
+    }
+
+    // F1 of the counts of all the languages together
+    pub(super) fn micro_f1(&self) -> f64 {
+        let (mut tp, mut gold, mut pred) = (0, 0, 0);
+        for lang in self.labels() {
+            let counts = self.counts(lang);
+            tp += counts.0;
+            gold += counts.1;
+            pred += counts.2;
+        }
+        let (p, r) = (ratio(tp, pred), ratio(tp, gold));
+        if p + r == 0.0 {
+            0.0
+        } else {
+            2.0 * p * r / (p + r)
+        }
+    }
+
+    pub(super) fn write_metrics<W: Write>(
+        &self,
+        writer: &mut W,
+        precision: usize,
+        min_support: usize,
+    ) -> io::Result<()> {
+        writeln!(writer, "lang\tprecision\trecall\tf1\tsupport")?;
+        for lang in self.reported_labels(min_support) {
+            let (p, r, f1, gold) = self.metrics(lang);
+            writeln!(
+                writer,
+                "{lang}\t{p:.precision$}\t{r:.precision$}\t{f1:.precision$}\t{gold}"
+            )?;
+        }
+        writeln!(writer)?;
+        writeln!(writer, "accuracy\t{:.precision$}", self.accuracy())?;
+        writeln!(
+            writer,
+            "macro-f1\t{:.precision$}",
+            self.macro_f1(min_support)
+        )?;
+        writeln!(writer, "micro-f1\t{:.precision$}", self.micro_f1())
+    }
+
+    // Structured report with the same metrics as write_metrics
+    pub(super) fn report(&self, min_support: usize) -> serde_json::Value {
+        let languages: Vec<_> = self
+            .reported_labels(min_support)
+            .into_iter()
+            .map(|lang| {
+                let (p, r, f1, gold) = self.metrics(lang);
+                json!({
+                    "lang": lang.to_string(),
+                    "precision": p,
+                    "recall": r,
+                    "f1": f1,
+                    "support": gold,
+                })
+            })
+            .collect();
+        json!({
+            "accuracy": self.accuracy(),
+            "macro_f1": self.macro_f1(min_support),
+            "micro_f1": self.micro_f1(),
+            "total": self.total,
+            "languages": languages,
+        })
+    }
+
+    fn top_confused(&self, n: usize) -> Vec<(&(Lang, Lang), &usize)> {
+        let mut confused: Vec<_> = self
+            .confusion
+            .iter()
+            .filter(|((gold, pred), _)| gold != pred)
+            .collect();
+        confused.sort_by(|a, b| {
+            b.1.cmp(a.1)
+                .then_with(|| a.0 .0.to_string().cmp(&b.0 .0.to_string()))
+        });
+        confused.truncate(n);
+        confused
+    }
+
+    fn write_top_confused<W: Write>(&self, writer: &mut W, n: usize) -> io::Result<()> {
+        writeln!(writer, "gold\tpredicted\tcount")?;
+        for ((gold, pred), count) in self.top_confused(n) {
+            writeln!(writer, "{gold}\t{pred}\t{count}")?;
+        }
+        Ok(())
+    }
+
+    // Write the confusion matrix with gold labels as rows and predictions as columns
+    fn write_confusion_matrix<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let labels = self.labels();
+        write!(writer, "gold\\predicted")?;
+        for lang in &labels {
+            write!(writer, "\t{lang}")?;
+        }
+        writeln!(writer)?;
+        for gold in &labels {
+            write!(writer, "{gold}")?;
+            for pred in &labels {
+                let count = self.confusion.get(&(*gold, *pred)).unwrap_or(&0);
+                write!(writer, "\t{count}")?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
+
+// Read the text and label columns of a tab separated file
+pub(super) fn read_labeled<R: BufRead>(
+    reader: R,
+    text_col: usize,
+    label_col: usize,
+) -> Result<(Vec<String>, Vec<Lang>)> {
+    let mut texts = Vec::new();
+    let mut labels = Vec::new();
+    for (i, line_res) in reader.lines().enumerate() {
+        let line = line_res?;
+        let fields: Vec<_> = line.split('\t').collect();
+        if text_col >= fields.len() || label_col >= fields.len() {
+            bail!(
+                "Line {} has {} columns, expected at least {}",
+                i + 1,
+                fields.len(),
+                text_col.max(label_col) + 1
+            );
+        }
+        let label = fields[label_col].trim();
+        labels.push(Lang::from_str(&label.to_lowercase()).with_context(|| {
+            format!("Language code '{label}' at line {} does not exist", i + 1)
+        })?);
+        texts.push(fields[text_col].to_string());
+    }
+    Ok((texts, labels))
+}
+
+pub(super) fn open_reader(p: &Path) -> Result<Box<dyn BufRead>> {
+    let file = File::open(p)
+        .with_context(|| format!("Error opening input file {} for reading", p.display()))?;
+    Ok(Box::new(BufReader::new(file)))
+}
+
+impl EvalCmd {
+    pub fn cli(self) -> Result<()> {
+        info!("Starting");
+        let now = Instant::now();
+
+        let mut relevant_langs = None;
+        if let Some(r) = &self.relevant_langs {
+            relevant_langs = Some(parse_langs(r).or_abort(1));
+        }
+        debug!("{:?}", self);
+        let model_dir = resolve_model_dir(&self.model_dir, relevant_langs.is_some())?;
+
+        let reader = match &self.input {
+            Some(p) => open_reader(p).or_abort(1),
+            None => Box::new(io::stdin().lock()),
+        };
+        let (texts, gold) = read_labeled(reader, self.text_col, self.label_col).or_abort(1);
+        info!("Read {} labeled texts", texts.len());
+
+        info!("Loading model");
+        let identifier =
+            Identifier::load(&model_dir, relevant_langs, Some(!self.not_strict)).or_abort(1);
+        let preds = identifier
+            .par_identify_str(texts.par_iter().map(String::as_str), self.ignore_confidence);
+
+        let mut evaluation = Evaluation::default();
+        for (gold, (pred, _)) in gold.into_iter().zip(preds) {
+            evaluation.add(gold, pred);
+        }
+
+        let mut stdout = io::stdout().lock();
+        match self.output_format {
+            EvalFormat::Tsv => {
+                evaluation.write_metrics(&mut stdout, self.precision, self.min_support)?;
+                if let Some(n) = self.top_confused {
+                    writeln!(stdout)?;
+                    evaluation.write_top_confused(&mut stdout, n)?;
+                }
+            }
+            EvalFormat::Json => {
+                let mut report = evaluation.report(self.min_support);
+                if let Some(n) = self.top_confused {
+                    report["top_confused"] = evaluation
+                        .top_confused(n)
+                        .into_iter()
+                        .map(|((gold, pred), count)| {
+                            json!({"gold": gold.to_string(), "predicted": pred.to_string(), "count": count})
+                        })
+                        .collect();
+                }
+                writeln!(stdout, "{report}")?;
+            }
+        }
+        if let Some(p) = &self.confusion_matrix {
+            let file = File::create(p)
+                .with_context(|| format!("Error opening output file {} for writing", p.display()))
+                .or_abort(1);
+            evaluation.write_confusion_matrix(&mut BufWriter::new(file))?;
+        }
+
+        info!("Finished");
+        info!("Elapsed time: {:.2?}", now.elapsed());
+        Ok(())
+    }
+}
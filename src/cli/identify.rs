@@ -1,20 +1,44 @@
-use std::fs::File;
-use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
-use std::str::FromStr;
 use std::time::Instant;
 
 use anyhow::{Context, Result};
-use clap::Args;
+use clap::{Args, ValueEnum};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use indicatif::{ProgressBar, ProgressStyle};
 use itertools::Itertools;
-use log::{debug, info};
+use log::{debug, info, warn};
+use rayon::prelude::*;
+use serde_json::json;
 
+use super::{parse_langs, resolve_model_dir};
 use crate::identifier::Identifier;
-#[cfg(feature = "python")]
-use crate::python::module_path;
-use crate::utils::Abort;
+use crate::utils::{truncate_at_word, Abort};
 use heliport_model::Lang;
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    /// Tab separated values
+    Tsv,
+    /// One JSON object per line
+    Json,
+    /// One JSON object per line, with the index of the input line
+    Ndjson,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum InputFormat {
+    /// Uncompressed text
+    Raw,
+    /// Gzip compressed text
+    Gz,
+    /// Zstandard compressed text
+    Zst,
+}
+
 #[derive(Args, Clone, Debug)]
 pub struct IdentifyCmd {
     #[arg(
@@ -50,11 +74,75 @@ pub struct IdentifyCmd {
     not_strict: bool,
     #[arg(long, short, default_value_t = 4, help = "Number of decimals precision when printing scores")]
     precision: usize,
+    #[arg(short = 'F', long, value_enum, default_value_t = OutputFormat::Tsv, help = "Output format")]
+    output_format: OutputFormat,
+    #[arg(long, help = "Include the input text in JSON and NDJSON output")]
+    include_text: bool,
+    #[arg(
+        long,
+        help = "Write the input text after the prediction, separated by a tab. In JSON and NDJSON output, same as '--include-text'"
+    )]
+    pass_through: bool,
+    #[arg(
+        short = 'k',
+        long,
+        help = "Print the top k most probable languages with their raw scores (lower is better)"
+    )]
+    topk: Option<usize>,
+    #[arg(long, help = "Show a progress bar on stderr")]
+    progress: bool,
+    #[arg(
+        long,
+        value_enum,
+        help = "Compression of the input, default: detected from the file extension ('.gz' or '.zst')"
+    )]
+    input_format: Option<InputFormat>,
+    #[arg(long, help = "Write gzip compressed output")]
+    compress_output: bool,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = Lang::from_bcp47,
+        help = "Only write the lines predicted as one of these languages. Specify a comma-separated list of language codes, optionally with script (e.g. 'spa' or 'spa_Latn')"
+    )]
+    filter_lang: Option<Vec<Lang>>,
+    #[arg(long, help = "Do not write anything for blank lines")]
+    skip_empty: bool,
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Do not write anything for lines shorter than this number of characters, after stripping whitespace"
+    )]
+    min_length: usize,
+    #[arg(
+        long,
+        help = "Truncate lines to this number of characters, at a word boundary if possible, before identification"
+    )]
+    max_length: Option<usize>,
+    #[arg(
+        long,
+        help = "Identify only the text in this column (starting at 0) of tab separated input. With '--pass-through', the prediction is written after all the columns"
+    )]
+    input_column: Option<usize>,
 
-    #[arg(help = "Input file, default: stdin")]
-    input_file: Option<PathBuf>,
-    #[arg(help = "Output file, default: stdout")]
+    #[arg(help = "Input files, processed sequentially, default: stdin")]
+    input_files: Vec<PathBuf>,
+    #[arg(
+        short = 'o',
+        long,
+        help = "Output file, '-' for stdout, default: stdout"
+    )]
     output_file: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Read the paths of the input files from a file, one per line"
+    )]
+    manifest: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Write the name of the input file before each prediction, separated by a tab. In JSON and NDJSON output, add it as 'filename'"
+    )]
+    include_filename: bool,
 
     #[arg(
         short,
@@ -71,10 +159,53 @@ pub struct IdentifyCmd {
     relevant_langs: Option<Vec<String>>,
 }
 
-fn open_reader(p: &Path) -> Result<Box<dyn BufRead>> {
+// Compression of the input, detected from the file extension if not provided
+fn input_format(p: &Path, format: Option<InputFormat>) -> InputFormat {
+    if let Some(f) = format {
+        return f;
+    }
+    match p.extension().and_then(|e| e.to_str()) {
+        Some("gz") => InputFormat::Gz,
+        Some("zst") => InputFormat::Zst,
+        _ => InputFormat::Raw,
+    }
+}
+
+// Wrap a reader with the decoder of its compression format
+fn decode_reader<R: Read + 'static>(reader: R, format: InputFormat) -> Result<Box<dyn BufRead>> {
+    Ok(match format {
+        InputFormat::Raw => Box::new(BufReader::new(reader)),
+        InputFormat::Gz => Box::new(BufReader::new(MultiGzDecoder::new(reader))),
+        InputFormat::Zst => Box::new(BufReader::new(zstd::Decoder::new(reader)?)),
+    })
+}
+
+// Open an input file, advancing the progress bar with the bytes read before decoding
+fn open_reader(p: &Path, format: InputFormat, progress: &ProgressBar) -> Result<Box<dyn BufRead>> {
     let file = File::open(&p)
         .with_context(|| format!("Error opening input file {} for reading", p.display()))?;
-    Ok(Box::new(BufReader::new(file)))
+    decode_reader(progress.wrap_read(file), format)
+}
+
+// Read the list of input files, ignoring blank lines
+fn read_manifest(p: &Path) -> Result<Vec<PathBuf>> {
+    let content = fs::read_to_string(p)
+        .with_context(|| format!("Error opening manifest file {} for reading", p.display()))?;
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+// Counters kept across all the inputs
+#[derive(Default)]
+struct RunStats {
+    // Lines processed so far, NDJSON indexes do not restart with each input
+    lines: usize,
+    // Lines truncated with '--max-length'
+    truncated: usize,
 }
 
 fn open_writer(p: &Path) -> Result<Box<dyn Write>> {
@@ -83,18 +214,6 @@ fn open_writer(p: &Path) -> Result<Box<dyn Write>> {
     Ok(Box::new(BufWriter::new(file)))
 }
 
-// Parse a list of language code strings to Lang enum
-fn parse_langs(langs_text: &Vec<String>) -> Result<Vec<Lang>> {
-    let mut langs = Vec::new();
-    for l in langs_text {
-        langs.push(
-            Lang::from_str(&l.to_lowercase())
-                .with_context(|| format!("Language code '{l}' does not exist"))?,
-        );
-    }
-    Ok(langs)
-}
-
 impl IdentifyCmd {
     pub fn cli(self) -> Result<()> {
         info!("Starting");
@@ -112,116 +231,390 @@ impl IdentifyCmd {
         debug!("{:?}", self);
 
         // Obtain model directory
-        let model_dir;
-        if let Some(m) = &self.model_dir {
-            // Use provided model dir
-            model_dir = m.clone();
-        } else {
-            // If user does not provide model dir and relevant languages
-            // are requested, default to .LanguageModels in the repo
-            // otherwise use python module path
-            if relevant_langs.is_some() {
-                model_dir = PathBuf::from("LanguageModels");
-            } else {
-                #[cfg(feature = "python")]
-                {
-                    model_dir = module_path().unwrap();
-                }
-                #[cfg(not(feature = "python"))]
-                {
-                    use anyhow::bail;
-                    bail!(
-                        "Python feature is not enabled, therefore model path needs to be provided"
-                    );
-                }
-            }
-        }
+        let model_dir = resolve_model_dir(&self.model_dir, relevant_langs.is_some())?;
 
-        let (input_file, output_file);
-        if let Some(p) = &self.input_file {
-            input_file = open_reader(&p).or_abort(1);
-        } else {
-            input_file = Box::new(io::stdin().lock());
+        // Input files from the arguments and the manifest, stdin if there are none
+        let mut input_files = self.input_files.clone();
+        if let Some(p) = &self.manifest {
+            input_files.extend(read_manifest(p).or_abort(1));
         }
-        if let Some(p) = &self.output_file {
+        // Fail early if any of the files does not exist
+        for p in &input_files {
+            File::open(p)
+                .with_context(|| format!("Error opening input file {} for reading", p.display()))
+                .or_abort(1);
+        }
+
+        let mut output_file: Box<dyn Write>;
+        if let Some(p) = self.output_file.as_ref().filter(|p| *p != Path::new("-")) {
             output_file = open_writer(&p).or_abort(1);
         } else {
             output_file = Box::new(io::stdout().lock());
         }
 
+        // Initialize global thread pool with the number of threads
+        // provided by the user, before model loading uses it
+        if self.threads > 0 {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(self.threads)
+                .build_global()
+                .or_abort(1);
+        }
+
+        let progress = self.progress_bar(&input_files).or_abort(1);
+
         info!("Loading model");
         // Load identifier
-        let identifier = Identifier::load(&model_dir, relevant_langs, Some(!self.not_strict)).or_abort(1);
+        let mut identifier = Identifier::load(&model_dir, relevant_langs, Some(!self.not_strict)).or_abort(1);
         if self.ignore_confidence {
             info!("Disabled confidence thresholds");
         }
 
-        // do not run on separated threads if multithreading is not requested
         if self.threads == 0 {
             info!("Running single-threaded");
-            self.run_single(identifier, input_file, output_file)
-                .or_abort(1);
         } else {
             info!("Running with {} threads", self.threads);
-            self.run_parallel(identifier, input_file, output_file)
+        }
+
+        if self.compress_output {
+            // Buffer before compressing, then finish the encoder explicitly
+            // so errors writing the gzip footer are not lost when dropping it
+            let encoder = GzEncoder::new(output_file, Compression::default());
+            let mut writer = BufWriter::new(encoder);
+            self.run_inputs(&mut identifier, &input_files, &mut writer, &progress)
+                .or_abort(1);
+            writer.flush().context("Error writing output")?;
+            let encoder = writer.into_inner().map_err(|e| e.into_error())?;
+            encoder
+                .finish()
+                .and_then(|mut w| w.flush())
+                .context("Error writing compressed output")?;
+        } else {
+            self.run_inputs(&mut identifier, &input_files, &mut output_file, &progress)
                 .or_abort(1);
+            output_file.flush().context("Error writing output")?;
         }
+        progress.finish();
 
         info!("Finished");
         info!("Elapsed time: {:.2?}", now.elapsed());
         Ok(())
     }
 
+    // Create the progress bar, hidden if not requested
+    // advanced by the bytes read from the inputs, before decompressing them,
+    // so the size is only known beforehand when reading from files
+    fn progress_bar(&self, input_files: &[PathBuf]) -> Result<ProgressBar> {
+        if !self.progress {
+            return Ok(ProgressBar::hidden());
+        }
+        if !input_files.is_empty() {
+            let style = ProgressStyle::with_template(
+                "{elapsed_precise} [{wide_bar}] {binary_bytes}/{binary_total_bytes} ({binary_bytes_per_sec}, ETA {eta})",
+            )?;
+            let mut total = 0;
+            for p in input_files {
+                total += fs::metadata(p)
+                    .with_context(|| {
+                        format!("Error reading metadata of input file {}", p.display())
+                    })?
+                    .len();
+            }
+            Ok(ProgressBar::new(total).with_style(style))
+        } else {
+            let style = ProgressStyle::with_template(
+                "{spinner} {elapsed_precise} {binary_bytes} ({binary_bytes_per_sec})",
+            )?;
+            Ok(ProgressBar::new_spinner().with_style(style))
+        }
+    }
+
+    // Identify all the input files in order, or stdin if there are none
+    fn run_inputs<W: Write>(
+        &self,
+        identifier: &mut Identifier,
+        input_files: &[PathBuf],
+        writer: &mut W,
+        progress: &ProgressBar,
+    ) -> Result<()> {
+        let mut stats = RunStats::default();
+        if input_files.is_empty() {
+            let format = self.input_format.unwrap_or(InputFormat::Raw);
+            let stdin = progress.wrap_read(io::stdin().lock());
+            let input_file: Box<dyn BufRead> = if format == InputFormat::Raw {
+                Box::new(stdin)
+            } else {
+                decode_reader(stdin, format)?
+            };
+            self.run(identifier, input_file, writer, "-", &mut stats)?;
+        }
+        for p in input_files {
+            info!("Processing '{}'", p.display());
+            let input_file = open_reader(p, input_format(p, self.input_format), progress)?;
+            let filename = p.to_string_lossy();
+            self.run(identifier, input_file, writer, &filename, &mut stats)?;
+        }
+        if stats.truncated > 0 {
+            warn!(
+                "Lines truncated to {} characters: {}",
+                self.max_length.unwrap_or_default(),
+                stats.truncated
+            );
+        }
+        Ok(())
+    }
+
+    // Identify all the lines of an input
+    // do not run on separated threads if multithreading is not requested
+    fn run<R, W>(
+        &self,
+        identifier: &mut Identifier,
+        reader: R,
+        writer: &mut W,
+        filename: &str,
+        stats: &mut RunStats,
+    ) -> Result<()>
+    where
+        R: BufRead,
+        W: Write,
+    {
+        if self.threads == 0 {
+            self.run_single(identifier, reader, writer, filename, stats)
+        } else {
+            self.run_parallel(identifier, reader, writer, filename, stats)
+        }
+    }
+
     // Run using the parallel identification method
     // read in batches
-    fn run_parallel<'a, R, W>(self, identifier: Identifier, reader: R, mut writer: W) -> Result<()>
+    fn run_parallel<R, W>(
+        &self,
+        identifier: &Identifier,
+        reader: R,
+        writer: &mut W,
+        filename: &str,
+        stats: &mut RunStats,
+    ) -> Result<()>
     where
         R: BufRead,
         W: Write,
     {
-        // Initialize global thread pool with the number of threads
-        // provided by the user
-        rayon::ThreadPoolBuilder::new()
-            .num_threads(self.threads)
-            .build_global()
-            .or_abort(1);
-
         // Initialize the reader iterator in batches
         let batches = reader.lines().chunks(self.batch_size);
 
         // Process each batch in parallel
+        let offset = stats.lines;
+        let mut index = 0;
         for batch_result in &batches {
             let batch: Vec<_> = batch_result.map(|line| line.or_abort(1)).collect();
-            for pred in identifier.par_identify(batch, self.ignore_confidence) {
-                self.print_result(&mut writer, &pred).or_abort(1);
+            // Skipped lines are dropped before identifying, keeping the index of the rest
+            let mut kept = Vec::with_capacity(batch.len());
+            for (i, line) in batch.iter().enumerate() {
+                let text = self.column(index + i, line)?;
+                if !self.skip(text) {
+                    kept.push((index + i, line.as_str(), text));
+                }
+            }
+            let truncated: Vec<_> = kept
+                .iter()
+                .map(|(i, _, text)| self.truncate(*i, text, &mut stats.truncated))
+                .collect();
+            if let Some(k) = self.topk {
+                let preds =
+                    identifier.par_identify_topk(truncated.par_iter().map(|t| t.to_string()), k);
+                for ((i, line, text), pred) in kept.iter().zip(preds) {
+                    self.print_topk(writer, filename, offset + i, line, text, &pred)
+                        .or_abort(1);
+                }
+            } else {
+                let preds = identifier
+                    .par_identify_str(truncated.par_iter().copied(), self.ignore_confidence);
+                for ((i, line, text), pred) in kept.iter().zip(preds) {
+                    self.print_result(writer, filename, offset + i, line, text, &pred)
+                        .or_abort(1);
+                }
             }
+            index += batch.len();
         }
+        stats.lines += index;
         Ok(())
     }
 
     // Run using the single-threaded indetification method
-    fn run_single<R, W>(self, mut identifier: Identifier, reader: R, mut writer: W) -> Result<()>
+    fn run_single<R, W>(
+        &self,
+        identifier: &mut Identifier,
+        reader: R,
+        writer: &mut W,
+        filename: &str,
+        stats: &mut RunStats,
+    ) -> Result<()>
     where
         R: BufRead,
         W: Write,
     {
         // Process line by line
-        for line_res in reader.lines() {
+        let offset = stats.lines;
+        for (index, line_res) in reader.lines().enumerate() {
             let line = line_res?;
-            let pred = identifier.identify(&line, self.ignore_confidence);
-            self.print_result(&mut writer, &pred)?;
+            let text = self.column(index, &line)?;
+            // Skipped lines are not identified
+            if !self.skip(text) {
+                let truncated = self.truncate(index, text, &mut stats.truncated);
+                if let Some(k) = self.topk {
+                    let pred = identifier.identify_topk(truncated, k);
+                    self.print_topk(writer, filename, offset + index, &line, text, &pred)?;
+                } else {
+                    let pred = identifier.identify(truncated, self.ignore_confidence);
+                    self.print_result(writer, filename, offset + index, &line, text, &pred)?;
+                }
+            }
+            stats.lines += 1;
         }
         Ok(())
     }
 
-    fn print_result<W>(&self, writer: &mut W, pred: &(Lang, f32)) -> io::Result<()>
+    // Obtain the text to be identified, the whole line or one of its columns
+    fn column<'a>(&self, index: usize, line: &'a str) -> Result<&'a str> {
+        let Some(column) = self.input_column else {
+            return Ok(line);
+        };
+        line.split('\t')
+            .nth(column)
+            .with_context(|| format!("Line {} does not have column {column}", index + 1))
+    }
+
+    // Truncate the text to be identified if it is too long, counting the truncated lines
+    // the original line is still the one written in the output
+    fn truncate<'a>(&self, index: usize, text: &'a str, count: &mut usize) -> &'a str {
+        let Some(max_length) = self.max_length else {
+            return text;
+        };
+        let truncated = truncate_at_word(text, max_length);
+        if truncated.len() < text.len() {
+            debug!("Line {} truncated to {max_length} characters", index + 1);
+            *count += 1;
+        }
+        truncated
+    }
+
+    // Whether the line has to be skipped because it is blank or too short
+    fn skip(&self, text: &str) -> bool {
+        let text = text.trim();
+        (self.skip_empty && text.is_empty()) || text.chars().count() < self.min_length
+    }
+
+    // Whether the line has to be written according to the language filter
+    fn keep(&self, lang: Lang) -> bool {
+        match &self.filter_lang {
+            // predictions are collapsed, so varieties like 'bos' have to be collapsed too
+            Some(langs) => langs.iter().any(|l| l.collapse() == lang),
+            None => true,
+        }
+    }
+
+    fn print_result<W>(
+        &self,
+        writer: &mut W,
+        filename: &str,
+        index: usize,
+        line: &str,
+        text: &str,
+        pred: &(Lang, f32),
+    ) -> io::Result<()>
     where
         W: Write,
     {
-        if self.print_scores {
-            writeln!(writer, "{}\t{:.*}", pred.0, self.precision, pred.1)
-        } else {
-            writeln!(writer, "{}", pred.0)
+        if !self.keep(pred.0) {
+            return Ok(());
+        }
+        match self.output_format {
+            OutputFormat::Tsv => {
+                if self.include_filename {
+                    write!(writer, "{filename}\t")?;
+                }
+                // With a column as input, the prediction is a new column after the original ones
+                if self.pass_through && self.input_column.is_some() {
+                    write!(writer, "{line}\t")?;
+                }
+                match (
+                    self.print_scores,
+                    self.pass_through && self.input_column.is_none(),
+                ) {
+                    (true, true) => {
+                        writeln!(writer, "{}\t{:.*}\t{text}", pred.0, self.precision, pred.1)
+                    }
+                    (true, false) => writeln!(writer, "{}\t{:.*}", pred.0, self.precision, pred.1),
+                    (false, true) => writeln!(writer, "{}\t{text}", pred.0),
+                    (false, false) => writeln!(writer, "{}", pred.0),
+                }
+            }
+            OutputFormat::Json | OutputFormat::Ndjson => {
+                let mut object = json!({"lang": pred.0.to_string(), "score": pred.1});
+                if self.output_format == OutputFormat::Ndjson {
+                    object["index"] = index.into();
+                }
+                if self.include_text || self.pass_through {
+                    object["text"] = text.into();
+                }
+                if self.include_filename {
+                    object["filename"] = filename.into();
+                }
+                writeln!(writer, "{object}")
+            }
+        }
+    }
+
+    // Print the top k predictions of a line, scores are always printed
+    fn print_topk<W>(
+        &self,
+        writer: &mut W,
+        filename: &str,
+        index: usize,
+        line: &str,
+        text: &str,
+        preds: &[(Lang, f32)],
+    ) -> io::Result<()>
+    where
+        W: Write,
+    {
+        // Filter by the most probable language
+        if preds.first().is_some_and(|(lang, _)| !self.keep(*lang)) {
+            return Ok(());
+        }
+        match self.output_format {
+            OutputFormat::Tsv => {
+                let scores = preds
+                    .iter()
+                    .map(|(lang, score)| format!("{lang}\t{score:.*}", self.precision))
+                    .join("\t");
+                if self.include_filename {
+                    write!(writer, "{filename}\t")?;
+                }
+                // Text goes first, as the number of predictions can be lower than k
+                if self.pass_through {
+                    writeln!(writer, "{line}\t{scores}")
+                } else {
+                    writeln!(writer, "{scores}")
+                }
+            }
+            OutputFormat::Json | OutputFormat::Ndjson => {
+                let topk: Vec<_> = preds
+                    .iter()
+                    .map(|(lang, score)| json!({"lang": lang.to_string(), "score": score}))
+                    .collect();
+                let mut object = json!({ "topk": topk });
+                if self.output_format == OutputFormat::Ndjson {
+                    object["index"] = index.into();
+                }
+                if self.include_text || self.pass_through {
+                    object["text"] = text.into();
+                }
+                if self.include_filename {
+                    object["filename"] = filename.into();
+                }
+                writeln!(writer, "{object}")
+            }
         }
     }
 }
@@ -0,0 +1,124 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use itertools::Itertools;
+use log::info;
+use serde_json::json;
+use strum::{EnumCount, IntoEnumIterator};
+
+use super::resolve_model_dir;
+use crate::utils::Abort;
+use heliport_model::{Lang, Model, ModelNgram, OrderNgram};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum InfoFormat {
+    /// Human readable text
+    Text,
+    /// JSON object
+    Json,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct InfoCmd {
+    #[arg(
+        short,
+        long,
+        help = "Model directory containing binarized model. Default is Python module path"
+    )]
+    model_dir: Option<PathBuf>,
+    #[arg(short = 'F', long, value_enum, default_value_t = InfoFormat::Text, help = "Output format")]
+    format: InfoFormat,
+}
+
+// Read the format version from the header of a binary model file
+fn read_format_version(p: &Path) -> Result<u16> {
+    let mut header = Vec::with_capacity(ModelNgram::FORMAT_MAGIC.len() + 2);
+    File::open(p)
+        .with_context(|| format!("Could not open model file '{}'", p.display()))?
+        .take(header.capacity() as u64)
+        .read_to_end(&mut header)?;
+    ModelNgram::format_version(&header)
+        .with_context(|| format!("Error reading model '{}'", p.display()))
+}
+
+impl InfoCmd {
+    pub fn cli(self) -> Result<()> {
+        let model_dir = resolve_model_dir(&self.model_dir, false)?;
+
+        // Size and version of each binary file
+        let mut files_size = 0;
+        let mut versions = Vec::with_capacity(OrderNgram::COUNT);
+        for order in OrderNgram::iter() {
+            let path = model_dir.join(format!("{order}.bin"));
+            let metadata = fs::metadata(&path)
+                .with_context(|| format!("Model file '{}' could not be found", path.display()))
+                .or_abort(1);
+            files_size += metadata.len();
+            versions.push(read_format_version(&path).or_abort(1));
+        }
+        versions.dedup();
+
+        info!("Loading model");
+        let model = Model::load(&model_dir, false, false, None).or_abort(1);
+        let stats = model.stats();
+        let mut langs: Vec<_> = model.languages().into_iter().collect();
+        langs.sort_by_key(|lang| *lang as usize);
+        // Thresholds are only for macrolangs
+        let thresholds: Vec<(Lang, f32)> = Lang::iter()
+            .map(|lang| (lang, model.confidence.get(lang)))
+            .filter(|(_, conf)| *conf != 0.0)
+            .collect();
+
+        let mut stdout = io::stdout().lock();
+        match self.format {
+            InfoFormat::Text => {
+                writeln!(stdout, "Model directory: {}", model_dir.display())?;
+                writeln!(stdout, "Format version: {}", versions.iter().join(", "))?;
+                writeln!(stdout, "Number of languages: {}", langs.len())?;
+                writeln!(
+                    stdout,
+                    "Binary files size: {:.2} MiB",
+                    files_size as f64 / (1024.0 * 1024.0)
+                )?;
+                write!(stdout, "{stats}")?;
+                if thresholds.is_empty() {
+                    writeln!(stdout, "Confidence thresholds: not present")?;
+                } else {
+                    writeln!(
+                        stdout,
+                        "Confidence thresholds ({} languages):",
+                        thresholds.len()
+                    )?;
+                    for (lang, conf) in &thresholds {
+                        writeln!(stdout, "  {lang}\t{conf}")?;
+                    }
+                }
+            }
+            InfoFormat::Json => {
+                let orders: serde_json::Map<_, _> = stats
+                    .order_entries
+                    .iter()
+                    .map(|(order, entries)| (order.to_string(), (*entries).into()))
+                    .collect();
+                let confidence: serde_json::Map<_, _> = thresholds
+                    .iter()
+                    .map(|(lang, conf)| (lang.to_string(), (*conf).into()))
+                    .collect();
+                let object = json!({
+                    "model_dir": model_dir.display().to_string(),
+                    "format_version": versions,
+                    "languages": langs.iter().map(Lang::to_string).collect::<Vec<_>>(),
+                    "files_size": files_size,
+                    "vocab_size": stats.vocab_size,
+                    "order_entries": orders,
+                    "confidence_thresholds": confidence,
+                });
+                writeln!(stdout, "{object}")?;
+            }
+        }
+        Ok(())
+    }
+}
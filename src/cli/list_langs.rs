@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use serde_json::json;
+use strum::IntoEnumIterator;
+
+use crate::utils::Abort;
+use heliport_model::{Lang, Model, OrderNgram};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum ListFormat {
+    /// Tab separated table with a header
+    Tsv,
+    /// JSON array of objects
+    Json,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct ListLangsCmd {
+    #[arg(short = 'F', long, value_enum, default_value_t = ListFormat::Tsv, help = "Output format")]
+    format: ListFormat,
+    #[arg(
+        long,
+        help = "List only languages written in this script (ISO 15924 code, e.g. 'Arab')"
+    )]
+    filter_script: Option<String>,
+    #[arg(
+        long,
+        help = "List only languages that have model data in this directory, binarized or plain text"
+    )]
+    in_model: Option<PathBuf>,
+}
+
+// Languages with data in a model directory
+// binarized models are preferred over plain text if both are present
+fn model_langs(model_dir: &Path) -> Result<HashSet<Lang>> {
+    let order = OrderNgram::Word;
+    if model_dir.join(format!("{order}.bin")).exists() {
+        let model = Model::load_partial(model_dir, &[order], false, None)
+            .with_context(|| format!("Could not load model from '{}'", model_dir.display()))?;
+        Ok(model.languages())
+    } else {
+        Ok(Lang::iter()
+            .filter(|lang| model_dir.join(format!("{lang}.{order}.model")).exists())
+            .collect())
+    }
+}
+
+impl ListLangsCmd {
+    pub fn cli(self) -> Result<()> {
+        let in_model = self
+            .in_model
+            .as_ref()
+            .map(|dir| model_langs(dir).or_abort(1));
+
+        let langs: Vec<Lang> = Lang::iter()
+            .filter(|lang| match &self.filter_script {
+                Some(script) => lang.script().eq_ignore_ascii_case(script),
+                None => true,
+            })
+            .filter(|lang| in_model.as_ref().map_or(true, |l| l.contains(lang)))
+            .collect();
+
+        let mut stdout = io::stdout().lock();
+        match self.format {
+            ListFormat::Tsv => {
+                writeln!(stdout, "code\tiso639-3\tscript\tname")?;
+                for lang in langs {
+                    writeln!(
+                        stdout,
+                        "{lang}\t{}\t{}\t{}",
+                        lang.language_subtag(),
+                        lang.script(),
+                        lang.human_name()
+                    )?;
+                }
+            }
+            ListFormat::Json => {
+                let list: Vec<_> = langs
+                    .iter()
+                    .map(|lang| {
+                        json!({
+                            "code": lang.to_string(),
+                            "iso639_3": lang.language_subtag(),
+                            "script": lang.script(),
+                            "name": lang.human_name(),
+                        })
+                    })
+                    .collect();
+                writeln!(stdout, "{}", serde_json::Value::from(list))?;
+            }
+        }
+        Ok(())
+    }
+}
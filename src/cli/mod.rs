@@ -1,17 +1,40 @@
 mod binarize;
+mod compare_models;
+mod compute_thresholds;
 mod create_models;
+mod cross_validate;
+mod eval;
 mod identify;
+mod info;
+mod list_langs;
+#[cfg(any(feature = "grpc", feature = "serve"))]
+mod serve;
+mod stats;
+mod validate_corpus;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use env_logger::Env;
 use std::ffi::OsString;
+use std::path::PathBuf;
+use std::str::FromStr;
 
 use self::binarize::BinarizeCmd;
+use self::compare_models::CompareModelsCmd;
+use self::compute_thresholds::ComputeThresholdsCmd;
 use self::create_models::CreateModelCmd;
+use self::cross_validate::CrossValidateCmd;
+use self::eval::EvalCmd;
 use self::identify::IdentifyCmd;
+use self::info::InfoCmd;
+use self::list_langs::ListLangsCmd;
+#[cfg(any(feature = "grpc", feature = "serve"))]
+use self::serve::ServeCmd;
+use self::stats::StatsCmd;
+use self::validate_corpus::ValidateCorpusCmd;
 #[cfg(feature = "python")]
 use crate::python::module_path;
+use heliport_model::Lang;
 
 #[derive(Parser, Clone)]
 #[command(version, about, long_about = None)]
@@ -30,6 +53,60 @@ enum Commands {
     Identify(IdentifyCmd),
     #[command(about = "Create heliport models", alias = "create-models")]
     CreateModel(CreateModelCmd),
+    #[command(about = "Evaluate identification accuracy on labeled data")]
+    Eval(EvalCmd),
+    #[command(about = "List supported languages")]
+    ListLangs(ListLangsCmd),
+    #[command(about = "Show model statistics")]
+    Info(InfoCmd),
+    #[command(about = "Compute the language distribution of a corpus")]
+    Stats(StatsCmd),
+    #[command(about = "Compare the accuracy of two models on labeled data")]
+    CompareModels(CompareModelsCmd),
+    #[command(about = "Check training files before creating models")]
+    ValidateCorpus(ValidateCorpusCmd),
+    #[command(about = "Evaluate training parameters with k-fold cross-validation")]
+    CrossValidate(CrossValidateCmd),
+    #[command(about = "Compute confidence thresholds from labeled data")]
+    ComputeThresholds(ComputeThresholdsCmd),
+    #[cfg(any(feature = "grpc", feature = "serve"))]
+    #[command(about = "Run language identification servers")]
+    Serve(ServeCmd),
+}
+
+// Parse a list of language code strings to Lang enum
+fn parse_langs(langs_text: &Vec<String>) -> Result<Vec<Lang>> {
+    let mut langs = Vec::new();
+    for l in langs_text {
+        langs.push(
+            Lang::from_str(&l.to_lowercase())
+                .with_context(|| format!("Language code '{l}' does not exist"))?,
+        );
+    }
+    Ok(langs)
+}
+
+// Obtain the model directory to load from
+fn resolve_model_dir(model_dir: &Option<PathBuf>, relevant_langs: bool) -> Result<PathBuf> {
+    if let Some(m) = model_dir {
+        // Use provided model dir
+        return Ok(m.clone());
+    }
+    // If user does not provide model dir and relevant languages
+    // are requested, default to .LanguageModels in the repo
+    // otherwise use python module path
+    if relevant_langs {
+        return Ok(PathBuf::from("LanguageModels"));
+    }
+    #[cfg(feature = "python")]
+    {
+        Ok(module_path().unwrap())
+    }
+    #[cfg(not(feature = "python"))]
+    {
+        use anyhow::bail;
+        bail!("Python feature is not enabled, therefore model path needs to be provided");
+    }
 }
 
 pub fn cli_run<I, T>(os_args: I) -> Result<()>
@@ -59,5 +136,15 @@ where
         Commands::Binarize(cmd) => cmd.cli(),
         Commands::Identify(cmd) => cmd.cli(),
         Commands::CreateModel(cmd) => cmd.cli(),
+        Commands::Eval(cmd) => cmd.cli(),
+        Commands::ListLangs(cmd) => cmd.cli(),
+        Commands::Info(cmd) => cmd.cli(),
+        Commands::Stats(cmd) => cmd.cli(),
+        Commands::CompareModels(cmd) => cmd.cli(),
+        Commands::ValidateCorpus(cmd) => cmd.cli(),
+        Commands::CrossValidate(cmd) => cmd.cli(),
+        Commands::ComputeThresholds(cmd) => cmd.cli(),
+        #[cfg(any(feature = "grpc", feature = "serve"))]
+        Commands::Serve(cmd) => cmd.cli(),
     }
 }
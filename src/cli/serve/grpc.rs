@@ -0,0 +1,89 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use log::info;
+use tonic::{Request, Response, Status};
+
+use crate::identifier::Identifier;
+use heliport_grpc::language_identifier_server::{LanguageIdentifier, LanguageIdentifierServer};
+use heliport_grpc::{
+    IdentifyBatchRequest, IdentifyBatchResponse, IdentifyRequest, IdentifyResponse, Prediction,
+};
+use heliport_model::{Lang, Model};
+
+// The model is shared by all the requests
+// each request creates its own identifier, which is cheap
+struct GrpcService {
+    model: Arc<Model>,
+}
+
+fn response(preds: Vec<(Lang, f32)>) -> IdentifyResponse {
+    IdentifyResponse {
+        predictions: preds
+            .into_iter()
+            .map(|(lang, score)| Prediction {
+                lang: lang.to_string(),
+                score,
+            })
+            .collect(),
+    }
+}
+
+#[tonic::async_trait]
+impl LanguageIdentifier for GrpcService {
+    async fn identify(
+        &self,
+        request: Request<IdentifyRequest>,
+    ) -> Result<Response<IdentifyResponse>, Status> {
+        let request = request.into_inner();
+        let mut identifier = Identifier::new(self.model.clone());
+        // Do not block the async runtime while identifying
+        let preds = tokio::task::spawn_blocking(move || {
+            if request.topk > 0 {
+                identifier.identify_topk(&request.text, request.topk as usize)
+            } else {
+                vec![identifier.identify(&request.text, request.ignore_confidence)]
+            }
+        })
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(response(preds)))
+    }
+
+    async fn identify_batch(
+        &self,
+        request: Request<IdentifyBatchRequest>,
+    ) -> Result<Response<IdentifyBatchResponse>, Status> {
+        let request = request.into_inner();
+        let identifier = Identifier::new(self.model.clone());
+        let responses = tokio::task::spawn_blocking(move || {
+            if request.topk > 0 {
+                identifier
+                    .par_identify_topk(request.texts, request.topk as usize)
+                    .into_iter()
+                    .map(response)
+                    .collect()
+            } else {
+                identifier
+                    .par_identify(request.texts, request.ignore_confidence)
+                    .into_iter()
+                    .map(|pred| response(vec![pred]))
+                    .collect()
+            }
+        })
+        .await
+        .map_err(|e| Status::internal(e.to_string()))?;
+        Ok(Response::new(IdentifyBatchResponse { responses }))
+    }
+}
+
+// Run the gRPC server until it fails
+pub async fn run(addr: SocketAddr, model: Arc<Model>) -> Result<()> {
+    info!("gRPC server listening on {addr}");
+    tonic::transport::Server::builder()
+        .add_service(LanguageIdentifierServer::new(GrpcService { model }))
+        .serve(addr)
+        .await
+        .with_context(|| format!("Error running gRPC server on {addr}"))
+}
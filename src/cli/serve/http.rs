@@ -0,0 +1,147 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::{Json, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::Router;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::identifier::Identifier;
+use heliport_model::{Lang, Model};
+
+// Static description of the API, served at /openapi.json
+const OPENAPI_SCHEMA: &str = include_str!("openapi.json");
+
+#[derive(Deserialize)]
+struct IdentifyRequest {
+    text: String,
+    #[serde(default)]
+    ignore_confidence: bool,
+}
+
+#[derive(Deserialize)]
+struct BatchRequest {
+    texts: Vec<String>,
+    #[serde(default)]
+    ignore_confidence: bool,
+}
+
+#[derive(Serialize)]
+struct Prediction {
+    lang: String,
+    score: f32,
+}
+
+impl From<(Lang, f32)> for Prediction {
+    fn from((lang, score): (Lang, f32)) -> Self {
+        Self {
+            lang: lang.to_string(),
+            score,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct BatchResponse {
+    predictions: Vec<Prediction>,
+}
+
+#[derive(Serialize)]
+struct LanguageInfo {
+    code: String,
+    iso639_3: &'static str,
+    script: &'static str,
+    name: &'static str,
+}
+
+type ErrorResponse = (StatusCode, String);
+
+// Shared by all the requests, the list of languages does not change while serving
+#[derive(Clone)]
+struct AppState {
+    model: Arc<Model>,
+    languages: Arc<Vec<LanguageInfo>>,
+}
+
+// Do not block the async runtime while identifying
+async fn blocking<T, F>(f: F) -> Result<T, ErrorResponse>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+async fn identify(
+    State(state): State<AppState>,
+    Json(request): Json<IdentifyRequest>,
+) -> Result<Json<Prediction>, ErrorResponse> {
+    // Each request creates its own identifier, which is cheap
+    let mut identifier = Identifier::new(state.model);
+    let pred =
+        blocking(move || identifier.identify(&request.text, request.ignore_confidence)).await?;
+    Ok(Json(pred.into()))
+}
+
+async fn identify_batch(
+    State(state): State<AppState>,
+    Json(request): Json<BatchRequest>,
+) -> Result<Json<BatchResponse>, ErrorResponse> {
+    let identifier = Identifier::new(state.model);
+    let preds =
+        blocking(move || identifier.par_identify(request.texts, request.ignore_confidence)).await?;
+    Ok(Json(BatchResponse {
+        predictions: preds.into_iter().map(Prediction::from).collect(),
+    }))
+}
+
+// Languages of the model sorted by code
+fn language_infos(model: &Model) -> Vec<LanguageInfo> {
+    let mut langs: Vec<_> = model.languages().into_iter().collect();
+    langs.sort_by_key(|lang| *lang as usize);
+    langs
+        .into_iter()
+        .map(|lang| LanguageInfo {
+            code: lang.to_string(),
+            iso639_3: lang.language_subtag(),
+            script: lang.script(),
+            name: lang.human_name(),
+        })
+        .collect()
+}
+
+async fn languages(State(state): State<AppState>) -> Response {
+    Json(state.languages.as_slice()).into_response()
+}
+
+async fn openapi() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/json")], OPENAPI_SCHEMA)
+}
+
+// Run the HTTP server until it fails
+pub async fn run(addr: SocketAddr, model: Arc<Model>) -> Result<()> {
+    let state = AppState {
+        languages: Arc::new(language_infos(&model)),
+        model,
+    };
+    let app = Router::new()
+        .route("/identify", post(identify))
+        .route("/identify/batch", post(identify_batch))
+        .route("/languages", get(languages))
+        .route("/openapi.json", get(openapi))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Could not listen on {addr}"))?;
+    info!("HTTP server listening on {addr}");
+    axum::serve(listener, app)
+        .await
+        .with_context(|| format!("Error running HTTP server on {addr}"))
+}
@@ -0,0 +1,103 @@
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(feature = "serve")]
+mod http;
+
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use log::{debug, info};
+use tokio::task::JoinSet;
+
+use super::{parse_langs, resolve_model_dir};
+use crate::utils::Abort;
+use heliport_model::Model;
+
+#[derive(Args, Clone, Debug)]
+pub struct ServeCmd {
+    #[arg(long, default_value = "127.0.0.1", help = "Address to listen on")]
+    host: IpAddr,
+    #[cfg(feature = "serve")]
+    #[arg(
+        long,
+        help = "Port of the HTTP server. If no port is given, all the servers are started on their default ports [default: 8080]"
+    )]
+    port: Option<u16>,
+    #[cfg(feature = "grpc")]
+    #[arg(
+        long,
+        help = "Port of the gRPC server. If no port is given, all the servers are started on their default ports [default: 50051]"
+    )]
+    grpc_port: Option<u16>,
+    #[arg(
+        short,
+        long,
+        help = "Do not be strict when loading confidence thresholds (do not fail if one language is missing)"
+    )]
+    not_strict: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Model directory containing binarized model or plain text model. Default is Python module path or './LanguageModels' if relevant languages are requested"
+    )]
+    model_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        short = 'l',
+        value_delimiter = ',',
+        help = "Load only relevant languages. Specify a comma-separated list of language codes. Needs plain text model directory"
+    )]
+    relevant_langs: Option<Vec<String>>,
+}
+
+impl ServeCmd {
+    pub fn cli(self) -> Result<()> {
+        let mut relevant_langs = None;
+        if let Some(r) = &self.relevant_langs {
+            relevant_langs = Some(parse_langs(r).or_abort(1));
+        }
+        debug!("{:?}", self);
+        let model_dir = resolve_model_dir(&self.model_dir, relevant_langs.is_some())?;
+
+        info!("Loading model");
+        let model = Model::load(&model_dir, !self.not_strict, false, relevant_langs).or_abort(1);
+        // The model is shared by all the servers
+        let model = Arc::new(model);
+
+        #[allow(unused_mut)]
+        let mut ports_given = false;
+        #[cfg(feature = "serve")]
+        {
+            ports_given |= self.port.is_some();
+        }
+        #[cfg(feature = "grpc")]
+        {
+            ports_given |= self.grpc_port.is_some();
+        }
+
+        let runtime = tokio::runtime::Runtime::new().context("Could not start async runtime")?;
+        runtime.block_on(async {
+            let mut servers = JoinSet::new();
+            #[cfg(feature = "serve")]
+            if let Some(port) = self.port.or((!ports_given).then_some(8080)) {
+                let addr = SocketAddr::new(self.host, port);
+                servers.spawn(http::run(addr, model.clone()));
+            }
+            #[cfg(feature = "grpc")]
+            if let Some(port) = self.grpc_port.or((!ports_given).then_some(50051)) {
+                let addr = SocketAddr::new(self.host, port);
+                servers.spawn(grpc::run(addr, model.clone()));
+            }
+
+            // Servers only finish on error, stop all of them when the first one fails
+            while let Some(result) = servers.join_next().await {
+                result??;
+            }
+            bail!("All servers stopped")
+        })
+    }
+}
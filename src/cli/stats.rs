@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use itertools::Itertools;
+use log::{debug, info};
+use rayon::prelude::*;
+use serde_json::json;
+use strum::IntoEnumIterator;
+
+use super::{parse_langs, resolve_model_dir};
+use crate::identifier::Identifier;
+use crate::utils::Abort;
+use heliport_model::{Lang, LangScores};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum StatsFormat {
+    /// Tab separated values
+    Tsv,
+    /// One JSON object per line
+    Json,
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct StatsCmd {
+    #[arg(short, long, help = "Input file, default: stdin")]
+    input: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        default_value_t = 100000,
+        help = "Number of text segments to pre-load for parallel processing"
+    )]
+    batch_size: usize,
+    #[arg(
+        short = 'k',
+        long,
+        help = "Use soft counts from the probabilities of the top k most probable languages"
+    )]
+    topk: Option<usize>,
+    #[arg(
+        short = 'c',
+        long,
+        help = "Ignore confidence thresholds. Predictions under the thresholds will not be labeled as 'und'"
+    )]
+    ignore_confidence: bool,
+    #[arg(
+        short = 'n',
+        long,
+        help = "Do not be strict when loading confidence thresholds (do not fail if one language is missing)"
+    )]
+    not_strict: bool,
+    #[arg(short = 'F', long, value_enum, default_value_t = StatsFormat::Tsv, help = "Output format")]
+    output_format: StatsFormat,
+    #[arg(
+        long,
+        short,
+        default_value_t = 2,
+        help = "Number of decimals precision when printing percentages"
+    )]
+    precision: usize,
+
+    #[arg(
+        short,
+        long,
+        help = "Model directory containing binarized model or plain text model. Default is Python module path or './LanguageModels' if relevant languages are requested"
+    )]
+    model_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        short = 'l',
+        value_delimiter = ',',
+        help = "Load only relevant languages. Specify a comma-separated list of language codes. Needs plain text model directory"
+    )]
+    relevant_langs: Option<Vec<String>>,
+}
+
+// Convert the raw scores (lower is better) of the top k languages
+// to a probability distribution with the softmax of the negated values
+fn soft_counts(preds: &[(Lang, f32)]) -> Vec<(Lang, f64)> {
+    // Texts that could not be identified only have a special language
+    if let [(lang, _)] = preds {
+        return vec![(*lang, 1.0)];
+    }
+    // Languages out of the top k get no probability
+    let mut scores = LangScores::new();
+    for lang in Lang::iter() {
+        scores.insert(lang, f32::INFINITY);
+    }
+    for (lang, score) in preds {
+        scores.insert(*lang, *score);
+    }
+    scores
+        .softmax()
+        .into_iter()
+        .filter(|(_, prob)| *prob > 0.0)
+        .collect()
+}
+
+impl StatsCmd {
+    pub fn cli(self) -> Result<()> {
+        info!("Starting");
+        let now = Instant::now();
+
+        let mut relevant_langs = None;
+        if let Some(r) = &self.relevant_langs {
+            relevant_langs = Some(parse_langs(r).or_abort(1));
+        }
+        debug!("{:?}", self);
+        let model_dir = resolve_model_dir(&self.model_dir, relevant_langs.is_some())?;
+
+        let reader: Box<dyn BufRead> = match &self.input {
+            Some(p) => Box::new(BufReader::new(
+                File::open(p)
+                    .with_context(|| {
+                        format!("Error opening input file {} for reading", p.display())
+                    })
+                    .or_abort(1),
+            )),
+            None => Box::new(io::stdin().lock()),
+        };
+
+        info!("Loading model");
+        let identifier =
+            Identifier::load(&model_dir, relevant_langs, Some(!self.not_strict)).or_abort(1);
+
+        // Count predictions in batches processed in parallel
+        let mut counts: HashMap<Lang, f64> = HashMap::new();
+        let mut total = 0;
+        for batch_result in &reader.lines().chunks(self.batch_size) {
+            let batch: Vec<_> = batch_result.map(|line| line.or_abort(1)).collect();
+            total += batch.len();
+            if let Some(k) = self.topk {
+                for preds in identifier.par_identify_topk(batch.into_par_iter(), k) {
+                    for (lang, prob) in soft_counts(&preds) {
+                        *counts.entry(lang).or_insert(0.0) += prob;
+                    }
+                }
+            } else {
+                let texts = batch.par_iter().map(String::as_str);
+                for (lang, _) in identifier.par_identify_str(texts, self.ignore_confidence) {
+                    *counts.entry(lang).or_insert(0.0) += 1.0;
+                }
+            }
+        }
+        info!("Identified {total} lines");
+
+        // Sort by count, most frequent first
+        let mut counts: Vec<_> = counts.into_iter().collect();
+        counts.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then_with(|| a.0.to_string().cmp(&b.0.to_string()))
+        });
+
+        let mut stdout = io::stdout().lock();
+        let precision = self.precision;
+        for (lang, count) in counts {
+            let pct = 100.0 * count / total as f64;
+            match self.output_format {
+                StatsFormat::Tsv => {
+                    if self.topk.is_some() {
+                        writeln!(stdout, "{lang}\t{count:.precision$}\t{pct:.precision$}")?;
+                    } else {
+                        writeln!(stdout, "{lang}\t{count}\t{pct:.precision$}")?;
+                    }
+                }
+                StatsFormat::Json => {
+                    // Hard counts are always integers
+                    let count = if self.topk.is_some() {
+                        json!(count)
+                    } else {
+                        json!(count as u64)
+                    };
+                    let object = json!({"lang": lang.to_string(), "count": count, "pct": pct});
+                    writeln!(stdout, "{object}")?;
+                }
+            }
+        }
+
+        info!("Finished");
+        info!("Elapsed time: {:.2?}", now.elapsed());
+        Ok(())
+    }
+}
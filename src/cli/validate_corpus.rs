@@ -0,0 +1,66 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::exit;
+
+use anyhow::{Context, Result};
+use clap::Args;
+use log::{error, info, warn};
+
+use crate::trainer::{validate_training_file, MIN_TRAINING_LINES};
+use crate::utils::Abort;
+
+#[derive(Args, Clone, Debug)]
+pub struct ValidateCorpusCmd {
+    #[arg(help = "Directory containing the '.train' files")]
+    input_dir: PathBuf,
+    #[arg(
+        long,
+        default_value_t = MIN_TRAINING_LINES,
+        help = "Warn about files with less lines than this"
+    )]
+    min_lines: usize,
+}
+
+impl ValidateCorpusCmd {
+    pub fn cli(self) -> Result<()> {
+        let mut files: Vec<PathBuf> = fs::read_dir(&self.input_dir)
+            .with_context(|| format!("Could not read directory '{}'", self.input_dir.display()))
+            .or_abort(1)
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<io::Result<_>>()?;
+        files.retain(|p| p.extension().is_some_and(|ext| ext == "train"));
+        files.sort();
+        info!("Validating {} files", files.len());
+
+        // Print the status of each file, details are logged
+        let mut stdout = io::stdout().lock();
+        let mut errors = 0;
+        for file in &files {
+            let status = match validate_training_file(file, self.min_lines) {
+                Ok(report) => {
+                    for warning in &report.warnings {
+                        warn!("{}: {warning}", file.display());
+                    }
+                    if report.warnings.is_empty() {
+                        "ok"
+                    } else {
+                        "warning"
+                    }
+                }
+                Err(e) => {
+                    error!("{}: {e:#}", file.display());
+                    errors += 1;
+                    "error"
+                }
+            };
+            writeln!(stdout, "{}\t{status}", file.display())?;
+        }
+
+        if errors > 0 {
+            error!("{errors} files are not valid");
+            exit(1);
+        }
+        Ok(())
+    }
+}
@@ -1,24 +1,92 @@
-use std::collections::BTreeMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use log::{debug, warn};
 use ordered_float::OrderedFloat;
 use rayon::prelude::*;
 use shingles::AsShingles;
 use strum::{EnumCount, IntoEnumIterator};
+use unicode_normalization::UnicodeNormalization;
 
 #[cfg(feature = "python")]
 use pyo3::pyclass;
 
-use crate::utils::{is_cjk_block, RE_NON_ALPHA};
-use heliport_model::Model;
+#[cfg(doc)]
+use crate::utils::sentence_splitter_default;
+use crate::utils::{detect_predominant_script, is_cjk_block, is_kana_block, RE_NON_ALPHA, RE_URL};
 use heliport_model::{Lang, LangBitmap, LangScores};
+use heliport_model::{LanguageModel, Model, OrderNgram};
+
+/// Predicted language of a text and its score
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IdentificationResult {
+    pub lang: Lang,
+    pub score: f32,
+}
+
+impl From<(Lang, f32)> for IdentificationResult {
+    fn from((lang, score): (Lang, f32)) -> Self {
+        Self { lang, score }
+    }
+}
+
+/// Outcome of the scoring of a text
+enum Scoring {
+    /// Language scores have been computed
+    Done,
+    /// There are no words to be scored
+    Empty,
+    /// Text is shorter than the minimum length
+    TooShort,
+}
+
+/// Unicode normalization applied to the text before scoring
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum NormalizationForm {
+    /// Leave the text as is
+    #[default]
+    None,
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+impl NormalizationForm {
+    fn normalize<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::None => Cow::Borrowed(text),
+            Self::Nfc => Cow::Owned(text.nfc().collect()),
+            Self::Nfd => Cow::Owned(text.nfd().collect()),
+            Self::Nfkc => Cow::Owned(text.nfkc().collect()),
+            Self::Nfkd => Cow::Owned(text.nfkd().collect()),
+        }
+    }
+}
 
 #[cfg_attr(feature = "python", pyclass)]
 pub struct Identifier {
-    model: Arc<Model>,
+    model: Arc<dyn LanguageModel>,
+    ignore_confidence: bool,
+    penalty: f32,
+    min_ngram: usize,
+    max_ngram: usize,
+    min_text_length: usize,
+    lang_restriction: Option<LangBitmap>,
+    script_filter: bool,
+    split_kana: bool,
+    // restriction applied to the text being scored
+    active_restriction: Option<LangBitmap>,
+    confidence_overrides: HashMap<Lang, f32>,
+    normalization: NormalizationForm,
+    strip_urls: bool,
+    coverage: f32,
     lang_scored: LangBitmap,
     lang_points: LangScores,
     word_scores: LangScores,
@@ -27,9 +95,96 @@ pub struct Identifier {
 
 /// A clone of Identifier creates new instances for all the members
 /// except the model, which is a pointer to avoid copying it.
+/// Configuration is kept.
 impl Clone for Identifier {
     fn clone(&self) -> Self {
-        Self::new(self.model.clone())
+        Self {
+            ignore_confidence: self.ignore_confidence,
+            penalty: self.penalty,
+            min_ngram: self.min_ngram,
+            max_ngram: self.max_ngram,
+            min_text_length: self.min_text_length,
+            lang_restriction: self.lang_restriction.clone(),
+            script_filter: self.script_filter,
+            split_kana: self.split_kana,
+            confidence_overrides: self.confidence_overrides.clone(),
+            normalization: self.normalization,
+            strip_urls: self.strip_urls,
+            ..Self::from_model(self.model.clone())
+        }
+    }
+}
+
+/// Builder to configure an [`Identifier`]
+#[derive(Default)]
+pub struct IdentifierBuilder {
+    model: Option<Arc<dyn LanguageModel>>,
+    ignore_confidence: bool,
+    penalty: Option<f32>,
+    max_ngram_order: Option<usize>,
+    min_text_length: usize,
+    language_restriction: Option<Vec<Lang>>,
+}
+
+impl IdentifierBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Model to be used for identification, this is required
+    pub fn model<M: LanguageModel + 'static>(mut self, model: Arc<M>) -> Self {
+        self.model = Some(model);
+        self
+    }
+
+    /// Always ignore confidence thresholds, regardless of what is requested in each call
+    pub fn ignore_confidence(mut self, ignore_confidence: bool) -> Self {
+        self.ignore_confidence = ignore_confidence;
+        self
+    }
+
+    /// Score given to languages that do not have a certain ngram
+    pub fn penalty(mut self, penalty: f32) -> Self {
+        self.penalty = Some(penalty);
+        self
+    }
+
+    /// Highest ngram order used to score words not found in the word model,
+    /// capped at the highest order available
+    pub fn max_ngram_order(mut self, order: usize) -> Self {
+        self.max_ngram_order = Some(order);
+        self
+    }
+
+    /// Texts shorter than this amount of characters, once cleaned, are not identified
+    pub fn min_text_length(mut self, min_chars: usize) -> Self {
+        self.min_text_length = min_chars;
+        self
+    }
+
+    /// Only consider the given languages during identification
+    pub fn language_restriction(mut self, langs: &[Lang]) -> Self {
+        self.language_restriction = Some(langs.to_vec());
+        self
+    }
+
+    pub fn build(self) -> Result<Identifier> {
+        let model = self
+            .model
+            .context("A model is required to build an Identifier")?;
+        let mut identifier = Identifier::from_model(model);
+        identifier.ignore_confidence = self.ignore_confidence;
+        identifier.set_min_text_length(self.min_text_length);
+        if let Some(penalty) = self.penalty {
+            identifier.set_penalty(penalty)?;
+        }
+        if let Some(order) = self.max_ngram_order {
+            identifier.set_max_ngram_order(order)?;
+        }
+        if let Some(langs) = self.language_restriction {
+            identifier.restrict_languages(&langs);
+        }
+        Ok(identifier)
     }
 }
 
@@ -42,9 +197,28 @@ impl Identifier {
         Ok(Self::new(Arc::new(Model::load(modelpath, s, false, langs)?)))
     }
 
-    pub fn new(model: Arc<Model>) -> Self {
+    /// Create an identifier with any kind of model,
+    /// for example a [`Model`] or a [`LazyModel`](heliport_model::LazyModel)
+    pub fn new<M: LanguageModel + 'static>(model: Arc<M>) -> Self {
+        Self::from_model(model)
+    }
+
+    fn from_model(model: Arc<dyn LanguageModel>) -> Self {
         Self {
             model: model,
+            ignore_confidence: false,
+            penalty: Self::PENALTY_VALUE,
+            min_ngram: 1,
+            max_ngram: Self::MAX_NGRAM,
+            min_text_length: 0,
+            lang_restriction: None,
+            script_filter: false,
+            split_kana: true,
+            active_restriction: None,
+            confidence_overrides: HashMap::new(),
+            normalization: NormalizationForm::None,
+            strip_urls: false,
+            coverage: 0.0,
             lang_scored: LangBitmap::new(),
             lang_points: LangScores::new(),
             word_scores: LangScores::new(),
@@ -52,10 +226,249 @@ impl Identifier {
         }
     }
 
+    /// Create an [`IdentifierBuilder`] to configure a new instance
+    pub fn builder() -> IdentifierBuilder {
+        IdentifierBuilder::new()
+    }
+
+    /// Set the score given to languages that do not have a certain ngram.
+    /// Defaults to 7.0, the value used in HeLI.
+    pub fn set_penalty(&mut self, v: f32) -> Result<&mut Self> {
+        if !v.is_finite() || v <= 0.0 {
+            bail!("Penalty value must be finite and positive, got '{v}'");
+        }
+        self.penalty = v;
+        Ok(self)
+    }
+
+    /// Set the highest ngram order used to score words not found in the word model.
+    /// Capped at the highest order available. Fails if it is lower than the lowest order.
+    pub fn set_max_ngram_order(&mut self, order: usize) -> Result<&mut Self> {
+        let order = order.clamp(1, OrderNgram::COUNT - 1);
+        if order < self.min_ngram {
+            bail!(
+                "Max ngram order '{order}' is lower than the min ngram order '{}'",
+                self.min_ngram
+            );
+        }
+        self.max_ngram = order;
+        Ok(self)
+    }
+
+    /// Set the lowest ngram order used to score words not found in the word model.
+    /// Fails if it is higher than the highest order.
+    pub fn set_min_ngram_order(&mut self, order: usize) -> Result<&mut Self> {
+        let order = order.clamp(1, OrderNgram::COUNT - 1);
+        if order > self.max_ngram {
+            bail!(
+                "Min ngram order '{order}' is higher than the max ngram order '{}'",
+                self.max_ngram
+            );
+        }
+        self.min_ngram = order;
+        Ok(self)
+    }
+
+    /// Set the minimum amount of characters, once non-alphabetic ones have been removed,
+    /// that a text needs to be identified. Shorter texts will be und.
+    pub fn set_min_text_length(&mut self, min_chars: usize) -> &mut Self {
+        self.min_text_length = min_chars;
+        self
+    }
+
+    /// Set the unicode normalization applied to texts before scoring.
+    /// Useful when texts may not be in the same form as the model (NFC).
+    pub fn set_unicode_normalization(&mut self, form: NormalizationForm) -> &mut Self {
+        self.normalization = form;
+        self
+    }
+
+    /// Remove URLs and email addresses from texts before scoring
+    pub fn set_strip_urls(&mut self, enabled: bool) -> &mut Self {
+        self.strip_urls = enabled;
+        self
+    }
+
+    /// Use a confidence threshold for a language
+    /// instead of the one provided by the model.
+    pub fn set_confidence_for(&mut self, lang: Lang, threshold: f32) -> &mut Self {
+        self.confidence_overrides.insert(lang, threshold);
+        self
+    }
+
+    /// Adjust the confidence thresholds of the predicted languages
+    /// to reach `target_precision` on a labeled dataset.
+    ///
+    /// For each language, the lowest threshold that reaches the target precision
+    /// is kept as an override of the model threshold.
+    /// Languages that can not reach it keep the current threshold.
+    pub fn calibrate(
+        &mut self,
+        labeled_data: &[(String, Lang)],
+        target_precision: f32,
+    ) -> Result<()> {
+        if !(target_precision > 0.0 && target_precision <= 1.0) {
+            bail!("Target precision must be between 0 and 1, got '{target_precision}'");
+        }
+        if labeled_data.is_empty() {
+            bail!("Calibration needs labeled data");
+        }
+
+        // Confidence of each prediction and whether it is correct, for each predicted lang
+        let mut predictions: HashMap<Lang, Vec<(f32, bool)>> = HashMap::new();
+        for (text, gold) in labeled_data {
+            if !matches!(self.score_langs(text), Scoring::Done) {
+                continue;
+            }
+            let (pred, confidence) = self.pick_winner(false, Some(f32::NEG_INFINITY));
+            predictions
+                .entry(pred)
+                .or_default()
+                .push((confidence, pred == gold.collapse()));
+        }
+
+        for (lang, mut preds) in predictions {
+            // Going from the most confident prediction to the least,
+            // each one is a candidate threshold
+            preds.sort_by(|a, b| b.0.total_cmp(&a.0));
+            let mut correct = 0;
+            let mut threshold = None;
+            for (i, (confidence, is_correct)) in preds.iter().enumerate() {
+                correct += *is_correct as usize;
+                // A threshold can not separate predictions with the same confidence
+                if preds.get(i + 1).is_some_and(|next| next.0 == *confidence) {
+                    continue;
+                }
+                if correct as f32 / (i + 1) as f32 >= target_precision {
+                    threshold = Some(*confidence);
+                }
+            }
+            match threshold {
+                Some(threshold) => {
+                    debug!("Calibrated threshold for '{lang}': {threshold}");
+                    self.set_confidence_for(lang, threshold);
+                }
+                None => warn!("Could not reach target precision for '{lang}'"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Adjust the confidence thresholds of the gold languages
+    /// to keep `target_recall` on a labeled dataset.
+    ///
+    /// For each language, the highest threshold that still keeps the target recall
+    /// is kept as an override of the model threshold.
+    /// Languages that can not reach it even without threshold keep the current one.
+    pub fn calibrate_recall(
+        &mut self,
+        labeled_data: &[(String, Lang)],
+        target_recall: f32,
+    ) -> Result<()> {
+        if !(target_recall > 0.0 && target_recall <= 1.0) {
+            bail!("Target recall must be between 0 and 1, got '{target_recall}'");
+        }
+        if labeled_data.is_empty() {
+            bail!("Calibration needs labeled data");
+        }
+
+        // Number of texts of each gold lang and confidence of their correct predictions
+        let mut predictions: HashMap<Lang, (usize, Vec<f32>)> = HashMap::new();
+        for (text, gold) in labeled_data {
+            let gold = gold.collapse();
+            let entry = predictions.entry(gold).or_default();
+            entry.0 += 1;
+            if !matches!(self.score_langs(text), Scoring::Done) {
+                continue;
+            }
+            let (pred, confidence) = self.pick_winner(false, Some(f32::NEG_INFINITY));
+            if pred == gold {
+                entry.1.push(confidence);
+            }
+        }
+
+        for (lang, (total, mut confidences)) in predictions {
+            // Number of correct predictions that have to be over the threshold
+            let needed = (target_recall * total as f32).ceil() as usize;
+            if needed == 0 || confidences.len() < needed {
+                warn!("Could not reach target recall for '{lang}'");
+                continue;
+            }
+            confidences.sort_by(|a, b| b.total_cmp(a));
+            let threshold = confidences[needed - 1];
+            debug!("Calibrated threshold for '{lang}': {threshold}");
+            self.set_confidence_for(lang, threshold);
+        }
+        Ok(())
+    }
+
+    /// Save the current confidence thresholds, including overrides,
+    /// in the same format as the model confidence thresholds file
+    pub fn save_thresholds(&self, path: &Path) -> Result<()> {
+        let file =
+            File::create(path).with_context(|| format!("Could not create '{}'", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        for lang in Lang::iter() {
+            let threshold = self.get_confidence(lang);
+            if !lang.is_special() && threshold != 0.0 {
+                writeln!(writer, "{lang}\t{threshold}")?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Only consider the given languages during identification,
+    /// the rest will always get the penalty value.
+    /// An empty list removes the restriction.
+    ///
+    /// Unlike loading the model only with certain languages,
+    /// this does not reduce memory usage but can be changed without reloading.
+    pub fn restrict_languages(&mut self, langs: &[Lang]) -> &mut Self {
+        if langs.is_empty() {
+            self.lang_restriction = None;
+        } else {
+            self.lang_restriction = Some(LangBitmap::from_langs(langs));
+        }
+        self
+    }
+
+    /// Only consider the languages written in the predominant script of each text.
+    ///
+    /// Languages that can be written in several scripts will only be considered
+    /// for the script they are most commonly written with, see [`Lang::script`].
+    pub fn set_script_filter(&mut self, enabled: bool) -> &mut Self {
+        self.script_filter = enabled;
+        self
+    }
+
+    /// Whether Hiragana and Katakana characters are separated from
+    /// Han characters as different words. Enabled by default.
+    pub fn set_split_kana(&mut self, enabled: bool) -> &mut Self {
+        self.split_kana = enabled;
+        self
+    }
+
+    /// Languages that can be written in a script
+    fn script_langs(script: &str) -> LangBitmap {
+        let mut langs = LangBitmap::new();
+        for lang in Lang::iter() {
+            // Han characters are also used in Japanese
+            let compatible = match script {
+                "Hani" => matches!(lang.script(), "Hani" | "Jpan"),
+                _ => lang.script() == script,
+            };
+            langs.set(&lang, compatible);
+        }
+        langs
+    }
+
     /// Get the most probable language according to the current language scores
-    fn pick_winner(&mut self, ignore_confidence: bool) -> (Lang, f32) {
+    ///
+    /// If `min_confidence` is given, it is used as threshold instead of the model ones.
+    fn pick_winner(&mut self, ignore_confidence: bool, min_confidence: Option<f32>) -> (Lang, f32) {
         // if only one lang is requested, just search for the minimum score (winner)
-        let mut score = Self::PENALTY_VALUE + 1.0;
+        let mut score = self.penalty + 1.0;
         let mut winner_lang = Lang::und;
 
         // Get the lang with minimum score
@@ -73,7 +486,7 @@ impl Identifier {
         // confidence is absolute difference with the second scoring language
         // only collapsed macrolangs can be taken into account
         if !ignore_confidence {
-            let mut second = Self::PENALTY_VALUE + 1.0;
+            let mut second = self.penalty + 1.0;
             for lang in Lang::iter() {
                 let points = self.lang_points.get(lang);
                 // compare only collapsed macrolangs
@@ -84,7 +497,8 @@ impl Identifier {
             // Compute absolute difference
             score = second - score;
             // Get the threshold, thresholds are only for macrolangs, so collapse
-            let threshold = self.model.confidence.get(winner_lang.collapse());
+            let threshold =
+                min_confidence.unwrap_or_else(|| self.get_confidence(winner_lang.collapse()));
             if threshold > score {
                 winner_lang = Lang::und;
             }
@@ -147,33 +561,47 @@ impl Identifier {
             let mut score;
             // Score the langs that have probabilities for this ngram
             for (lang, prob) in kiepro {
+                // Languages not allowed are left unscored, so they get penalized
+                if let Some(allowed) = &self.active_restriction {
+                    if !allowed.get(lang) {
+                        continue;
+                    }
+                }
                 score = self.word_scores.get(*lang);
                 self.word_scores.insert(lang.clone(), score + *prob);
                 self.lang_scored.set(lang, true);
             }
             // Penalize all the languages that do not have probabilities for this ngram
-            for i in 0..Lang::COUNT {
-                // instead of excluding scored langs with an if
-                // sum them all, multiplying by the negation of the bitmap
-                // which results in adding a 0 if it's scored
-                // this is faster, because of easier autovectorization?
-                self.word_scores.add_index(
-                    i,
-                    Self::PENALTY_VALUE * !self.lang_scored[i] as usize as f32,
-                );
-            }
+            self.word_scores
+                .add_unset(self.penalty, self.lang_scored.as_array());
             return true;
         }
         false
     }
 
     /// Read the text and obtain language scores based on found ngrams.
-    fn score_langs(&mut self, text: &str) -> bool {
+    fn score_langs(&mut self, text: &str) -> Scoring {
         // lowercase and remove non-alphabetic characters
         //TODO is it really remove all non alpha? because I found words with punctuation in
         //langmodel entries
         debug!("Input text: '{}'", text);
-        let lowercased = text.to_lowercase();
+        let text = if self.strip_urls {
+            RE_URL.replace_all(text, " ")
+        } else {
+            Cow::Borrowed(text)
+        };
+        self.active_restriction = self.lang_restriction.clone();
+        if self.script_filter {
+            if let Some(script) = detect_predominant_script(&text) {
+                debug!("Predominant script: {script}");
+                let script_langs = Self::script_langs(script);
+                self.active_restriction = match self.active_restriction.take() {
+                    Some(restriction) => Some(restriction & script_langs),
+                    None => Some(script_langs),
+                };
+            }
+        }
+        let lowercased = self.normalization.normalize(&text).to_lowercase();
         let replaced = RE_NON_ALPHA.replace_all(&lowercased, " ");
         self.heli_score.clear();
 
@@ -189,11 +617,12 @@ impl Identifier {
             // I do not know if this was intentional, but as a side effect, it separates
             // the groups of CJK unfied (commonly known chinese chars) from the hana and hangul
             // with a space. It seems to give better japanese identification in some cases.
+            // If kana splitting is disabled, they are treated as CJK.
             let is_cjk = if let Ok(is) = is_cjk_block(mystery_char) {
-                is
+                is || (!self.split_kana && is_kana_block(mystery_char))
             } else {
                 warn!("Could not find unicode block for '{}'", mystery_char);
-                return false;
+                return Scoring::Empty;
             };
 
             if is_cjk {
@@ -223,14 +652,20 @@ impl Identifier {
         // split_whitespace ignores them
         let mut words = mystery_text.split_whitespace().peekable();
 
+        // Texts without words are non-linguistic, regardless of the minimum length
         if words.peek().is_none() {
-            return false;
+            return Scoring::Empty;
+        }
+        if mystery_length < self.min_text_length {
+            debug!("Text shorter than {} chars", self.min_text_length);
+            return Scoring::TooShort;
         }
 
         self.lang_points.reset();
 
         let mut word_scored;
         let mut num_words = 0;
+        let mut num_words_found = 0;
         for word in words {
             debug!("Scoring '{}'", word);
             num_words += 1;
@@ -243,10 +678,14 @@ impl Identifier {
             if !word_scored {
                 debug!("Word has not been found");
                 let wordspace = format!(" {word} ");
-                for t in (1..Self::MAX_NGRAM + 1).rev() {
+                for t in (self.min_ngram..self.max_ngram + 1).rev() {
                     if word_scored {
                         break;
                     }
+                    // Orders that have not been loaded can be skipped
+                    if self.model[t].dic.is_empty() {
+                        continue;
+                    }
 
                     let mut grammaara = 0;
                     // Iterate over all possible ngrams of order t, over the current word
@@ -266,13 +705,15 @@ impl Identifier {
                 }
             }
 
+            num_words_found += word_scored as usize;
             // accumulate wordscores for the current word in the global lang points
-            self.lang_points.add(&self.word_scores);
+            self.lang_points.add_simd(&self.word_scores);
             debug!("Word scores: {:?}", self.word_scores);
             debug!("Lang points: {:?}", self.lang_points);
         }
 
         debug!("Finished scoring");
+        self.coverage = num_words_found as f32 / num_words as f32;
         // Choose the winner
         // the original code adds "und" but seems to not take it into consideration
         //self.lang_points.insert("und".to_string(), Self::PENALTY_VALUE + 1.0);
@@ -293,37 +734,204 @@ impl Identifier {
             self.lang_points.insert(lang, lang_score_norm);
 
             if cjk_pct > 0.5 && !lang.is_cjk() {
-                self.lang_points.insert(lang, Self::PENALTY_VALUE + 1.0);
+                self.lang_points.insert(lang, self.penalty + 1.0);
             }
         }
         debug!("Normalized lang points: {:?}", self.lang_points);
 
-        true
+        Scoring::Done
     }
 
     /// Identify the most probable language of a given text.
     ///
     /// Returns the language and score of the highest scoring language.
-    /// If there are no alphabetical characters or language can not be determined
-    /// it will return unk.
+    /// If there are no alphabetical characters it will return zxx with score 0,
+    /// if language can not be determined it will return und.
+    /// If the text is shorter than the minimum length, it will return und.
     pub fn identify(&mut self, text: &str, ignore_confidence: bool) -> (Lang, f32) {
-        if self.score_langs(text) {
-            self.pick_winner(ignore_confidence)
-        } else {
-            (Lang::zxx, Self::PENALTY_VALUE)
+        match self.score_langs(text) {
+            Scoring::Done => self.pick_winner(ignore_confidence || self.ignore_confidence, None),
+            Scoring::Empty => (Lang::zxx, 0.0),
+            Scoring::TooShort => (Lang::und, self.penalty),
+        }
+    }
+
+    /// Identify the most probable language of a given text,
+    /// returning it even if it does not reach the confidence threshold.
+    ///
+    /// The last value tells if the threshold has been met.
+    /// Score is the confidence value, or the raw score if confidence is ignored.
+    pub fn identify_with_fallback(&mut self, text: &str) -> (Lang, f32, bool) {
+        match self.score_langs(text) {
+            Scoring::Done => {
+                let (lang, score) = self.pick_winner(true, None);
+                if self.ignore_confidence {
+                    return (lang, score, true);
+                }
+                let (conf_lang, confidence) = self.pick_winner(false, None);
+                (lang, confidence, conf_lang != Lang::und)
+            }
+            Scoring::Empty => (Lang::zxx, 0.0, false),
+            Scoring::TooShort => (Lang::und, self.penalty, false),
+        }
+    }
+
+    /// Identify the most probable language of a given text
+    /// using `min_confidence` as threshold for all the languages.
+    ///
+    /// Model thresholds and ignoring confidence in the configuration do not apply
+    /// to this call.
+    pub fn identify_min_confidence(&mut self, text: &str, min_confidence: f32) -> (Lang, f32) {
+        match self.score_langs(text) {
+            Scoring::Done => self.pick_winner(false, Some(min_confidence)),
+            Scoring::Empty => (Lang::zxx, 0.0),
+            Scoring::TooShort => (Lang::und, self.penalty),
         }
     }
 
     /// Identify the top k most probable languages of a given text.
     ///
     /// Return the list of top k most probable languages and their scores.
-    /// If there are no alphabetical characters or language can not be determined
-    /// it will return unk.
+    /// If there are no alphabetical characters it will return zxx with score 0.
     pub fn identify_topk(&mut self, text: &str, k: usize) -> Vec<(Lang, f32)> {
-        if self.score_langs(text) {
-            self.rank_langs(k)
-        } else {
-            Vec::from([(Lang::zxx, Self::PENALTY_VALUE)])
+        match self.score_langs(text) {
+            Scoring::Done => self.rank_langs(k),
+            Scoring::Empty => Vec::from([(Lang::zxx, 0.0)]),
+            Scoring::TooShort => Vec::from([(Lang::und, self.penalty)]),
+        }
+    }
+
+    /// Identify the language of a document by majority vote of its sentences.
+    ///
+    /// The document is split with `splitter`, for example [`sentence_splitter_default`],
+    /// and each sentence is identified.
+    /// Returns the language with most votes and the average score of its sentences.
+    /// In case of a tie, the language that appears first in the document wins.
+    /// Sentences without alphabetic characters do not vote,
+    /// and undetermined sentences only count if no other language has votes.
+    pub fn identify_document<F>(&mut self, text: &str, splitter: F) -> (Lang, f32)
+    where
+        F: Fn(&str) -> Vec<&str>,
+    {
+        // Number of votes and accumulated score of each language, in order of appearance
+        let mut votes: Vec<(Lang, usize, f32)> = Vec::new();
+        let mut und_votes = (Lang::und, 0, 0.0);
+        for sentence in splitter(text) {
+            let (lang, score) = self.identify(sentence, false);
+            if lang == Lang::zxx {
+                continue;
+            }
+            if lang == Lang::und {
+                und_votes.1 += 1;
+                und_votes.2 += score;
+                continue;
+            }
+            if let Some(vote) = votes.iter_mut().find(|v| v.0 == lang) {
+                vote.1 += 1;
+                vote.2 += score;
+            } else {
+                votes.push((lang, 1, score));
+            }
+        }
+
+        let mut winner: Option<(Lang, usize, f32)> = None;
+        if votes.is_empty() && und_votes.1 > 0 {
+            winner = Some(und_votes);
+        }
+        for vote in votes {
+            if winner.map_or(true, |w| vote.1 > w.1) {
+                winner = Some(vote);
+            }
+        }
+        match winner {
+            Some((lang, count, score)) => (lang, score / count as f32),
+            None => (Lang::zxx, 0.0),
+        }
+    }
+
+    /// Identify the language of each paragraph of a text.
+    ///
+    /// Paragraphs are separated by an empty line,
+    /// either with `\n\n` or `\r\n\r\n`. Empty paragraphs are skipped.
+    pub fn identify_paragraph_wise(&mut self, text: &str) -> Vec<(Lang, f32)> {
+        text.split("\r\n\r\n")
+            .flat_map(|p| p.split("\n\n"))
+            .filter(|p| !p.trim().is_empty())
+            .map(|p| self.identify(p, false))
+            .collect()
+    }
+
+    /// Identify the language of each whitespace separated word of a text, in order.
+    ///
+    /// Words go through the same preprocessing as in [`Self::identify`],
+    /// so the ones without alphabetic characters will be zxx.
+    /// Confidence thresholds are rarely met by single words,
+    /// consider ignoring confidence in the [`IdentifierBuilder`].
+    pub fn annotate_words(&mut self, text: &str) -> Vec<(String, Lang, f32)> {
+        text.split_whitespace()
+            .map(|word| {
+                let (lang, score) = self.identify(word, false);
+                (word.to_string(), lang, score)
+            })
+            .collect()
+    }
+
+    /// Identify the language of windows of `window_words` words
+    /// moving `stride_words` words each step, to detect language changes within a text.
+    ///
+    /// Returns the offset of the first word of each window in the original text,
+    /// counting whitespace separated words, and its prediction.
+    /// Windows stop once the end of the text is reached.
+    pub fn identify_sliding_window(
+        &mut self,
+        text: &str,
+        window_words: usize,
+        stride_words: usize,
+    ) -> Vec<(usize, Lang, f32)> {
+        // Byte span of each word in the original text
+        let spans: Vec<(usize, usize)> = text
+            .split_whitespace()
+            .map(|word| {
+                let start = word.as_ptr() as usize - text.as_ptr() as usize;
+                (start, start + word.len())
+            })
+            .collect();
+
+        let mut windows = Vec::new();
+        if window_words == 0 {
+            return windows;
+        }
+        let mut start = 0;
+        while start < spans.len() {
+            let end = (start + window_words).min(spans.len());
+            let (lang, score) = self.identify(&text[spans[start].0..spans[end - 1].1], false);
+            windows.push((start, lang, score));
+            if end == spans.len() {
+                break;
+            }
+            start += stride_words.max(1);
+        }
+        windows
+    }
+
+    /// Fraction of the words of a text that have been found in any of the model orders.
+    ///
+    /// Low coverage means that the identification is not reliable.
+    /// Returns 0 if there is nothing to score.
+    pub fn ngram_coverage(&mut self, text: &str) -> f32 {
+        match self.score_langs(text) {
+            Scoring::Done => self.coverage,
+            Scoring::Empty | Scoring::TooShort => 0.0,
+        }
+    }
+
+    /// Obtain the raw scores of all the languages for a given text, lower is better.
+    ///
+    /// Returns `None` if there is nothing to score.
+    pub fn score_all(&mut self, text: &str) -> Option<LangScores> {
+        match self.score_langs(text) {
+            Scoring::Done => Some(self.lang_points.clone()),
+            Scoring::Empty | Scoring::TooShort => None,
         }
     }
 
@@ -334,52 +942,83 @@ impl Identifier {
     where
         I: IntoParallelIterator<Item = String>,
     {
-        // Each thread initializes with its own copy to the identifier object
-        thread_local! {
-            static IDENTIFIER_LOCAL: Mutex<Option<Identifier>> = Mutex::new(None);
-        }
-
         // Parallelize identification by the number of texts
+        // each rayon job initializes with its own copy of the identifier object
         texts
             .into_par_iter()
-            .map(|text| {
-                IDENTIFIER_LOCAL.with(|identifier| {
-                    // Only initialize the identifier once
-                    let mut identifier = identifier.lock().unwrap();
-                    if identifier.is_none() {
-                        *identifier = Some(self.clone());
-                    }
-                    identifier
-                        .as_mut()
-                        .unwrap()
-                        .identify(&text, ignore_confidence)
-                })
-            })
+            .map_init(
+                || self.clone(),
+                |identifier, text| identifier.identify(&text, ignore_confidence),
+            )
+            .collect()
+    }
+
+    /// Same as [`Self::par_identify`] but takes borrowed strings,
+    /// avoiding allocations when the texts are already in memory.
+    pub fn par_identify_str<'a, I>(&self, texts: I, ignore_confidence: bool) -> Vec<(Lang, f32)>
+    where
+        I: IntoParallelIterator<Item = &'a str>,
+    {
+        // Each rayon job initializes with its own copy of the identifier object
+        texts
+            .into_par_iter()
+            .map_init(
+                || self.clone(),
+                |identifier, text| identifier.identify(text, ignore_confidence),
+            )
+            .collect()
+    }
+
+    /// Parallel version of [`Self::identify_topk`]
+    ///
+    /// Takes an iterator of text instances and returns a [`Vec`] with the top k of each text
+    pub fn par_identify_topk<I>(&self, texts: I, k: usize) -> Vec<Vec<(Lang, f32)>>
+    where
+        I: IntoParallelIterator<Item = String>,
+    {
+        // Each rayon job initializes with its own copy of the identifier object
+        texts
+            .into_par_iter()
+            .map_init(
+                || self.clone(),
+                |identifier, text| identifier.identify_topk(&text, k),
+            )
             .collect()
     }
 
     /// Obtain confidence threshold for a language
     pub fn get_confidence(&self, lang: Lang) -> f32 {
-        self.model.confidence.get(lang)
+        match self.confidence_overrides.get(&lang) {
+            Some(threshold) => *threshold,
+            None => self.model.confidence().get(lang),
+        }
     }
 
     /// Obtain all confidence scores
-    pub fn get_confidence_all(&self) -> std::collections::HashMap<Lang, f32> {
-        self.model.confidence.to_map()
+    pub fn get_confidence_all(&self) -> HashMap<Lang, f32> {
+        let mut thresholds = self.model.confidence().to_map();
+        thresholds.extend(&self.confidence_overrides);
+        thresholds
     }
 
     /// Obtain all confidence scores languages in string format
-    pub fn get_confidence_all_string(&self) -> std::collections::HashMap<String, f32> {
-        self.model.confidence.to_string_map()
+    pub fn get_confidence_all_string(&self) -> HashMap<String, f32> {
+        self.get_confidence_all()
+            .into_iter()
+            .map(|(lang, threshold)| (lang.to_string(), threshold))
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::identifier::Identifier;
+    use crate::identifier::{Identifier, NormalizationForm};
     use crate::python;
+    use crate::utils::sentence_splitter_default;
     use heliport_model::lang::Lang;
+    use heliport_model::{LazyModel, Model, OrderNgram};
     use pyo3::Python;
+    use std::sync::{Arc, OnceLock};
 
     const INPUT_SENTS: [&str;13] = [
         "L'aigua clara",
@@ -468,4 +1107,365 @@ mod tests {
         let pred = identifier.identify("hello", true);
         assert!(pred.0 == Lang::sah);
     }
+
+    // Model shared by the tests that configure their own identifier, so it is only loaded once
+    fn shared_model() -> Arc<Model> {
+        static MODEL: OnceLock<Arc<Model>> = OnceLock::new();
+        MODEL
+            .get_or_init(|| {
+                Python::initialize();
+                Arc::new(
+                    Model::load(
+                        &python::module_path().expect("Python module needs to be installed"),
+                        true,
+                        false,
+                        None,
+                    )
+                    .expect("Could not load model, please run 'heliport bianrize' if you haven't"),
+                )
+            })
+            .clone()
+    }
+
+    #[test_log::test]
+    fn test_builder() {
+        assert!(Identifier::builder().build().is_err());
+        assert!(Identifier::builder()
+            .model(shared_model())
+            .penalty(-1.0)
+            .build()
+            .is_err());
+
+        let mut identifier = Identifier::builder()
+            .model(shared_model())
+            .ignore_confidence(true)
+            .min_text_length(4)
+            .language_restriction(&[Lang::cat, Lang::eng])
+            .build()
+            .unwrap();
+        assert_eq!(identifier.identify("Hola, ¿qué tal?", false).0, Lang::cat);
+        assert_eq!(identifier.identify("Hi!", false).0, Lang::und);
+        assert_eq!(identifier.identify("???", false).0, Lang::zxx);
+    }
+
+    #[test_log::test]
+    fn test_penalty() {
+        let mut identifier = Identifier::new(shared_model());
+        assert!(identifier.set_penalty(f32::NAN).is_err());
+        assert!(identifier.set_penalty(0.0).is_err());
+        identifier.set_min_text_length(4);
+        let (_, score) = identifier.set_penalty(9.0).unwrap().identify("Hi!", true);
+        assert_eq!(score, 9.0);
+    }
+
+    #[test_log::test]
+    fn test_ngram_orders() {
+        let mut identifier = Identifier::new(shared_model());
+        identifier
+            .set_min_ngram_order(3)
+            .unwrap()
+            .set_max_ngram_order(10)
+            .unwrap();
+        assert_eq!(identifier.max_ngram, 6);
+        // Inverted ranges are rejected and the previous orders are kept
+        assert!(identifier.set_max_ngram_order(2).is_err());
+        identifier.set_max_ngram_order(4).unwrap();
+        assert!(identifier.set_min_ngram_order(5).is_err());
+        assert_eq!((identifier.min_ngram, identifier.max_ngram), (3, 4));
+        assert_eq!(identifier.identify("L'aigua clara", true).0, Lang::cat);
+    }
+
+    #[test_log::test]
+    fn test_min_text_length() {
+        let mut identifier = Identifier::new(shared_model());
+        identifier.set_min_text_length(20);
+        assert_eq!(identifier.identify("L'aigua clara", true).0, Lang::und);
+        identifier.set_min_text_length(0);
+        assert_eq!(identifier.identify("L'aigua clara", true).0, Lang::cat);
+    }
+
+    #[test_log::test]
+    fn test_restrict_languages() {
+        let mut identifier = Identifier::new(shared_model());
+        identifier.restrict_languages(&[Lang::por, Lang::glg]);
+        assert_ne!(identifier.identify("Hola, ¿qué tal?", true).0, Lang::spa);
+        identifier.restrict_languages(&[]);
+        assert_eq!(identifier.identify("Hola, ¿qué tal?", true).0, Lang::spa);
+    }
+
+    #[test_log::test]
+    fn test_score_all() {
+        let mut identifier = Identifier::new(shared_model());
+        identifier.restrict_languages(&[Lang::por, Lang::glg]);
+        assert!(identifier.score_all("").is_none());
+        let scores = identifier.score_all("Hola, ¿qué tal?").unwrap();
+        assert!(scores.get(Lang::por) < scores.get(Lang::spa));
+    }
+
+    #[test_log::test]
+    fn test_par_identify_topk_str() {
+        let identifier = Identifier::new(shared_model());
+        let texts = vec![String::from("Hola, ¿qué tal?"), String::new()];
+        let preds = identifier.par_identify_topk(texts, 2);
+        assert_eq!(preds.len(), 2);
+        assert_eq!(preds[0].len(), 2);
+        assert_eq!(preds[1][0].0, Lang::zxx);
+        let preds = identifier.par_identify_str(["Bom dia", ""], true);
+        assert_eq!(preds.len(), 2);
+        assert_eq!(preds[1].0, Lang::zxx);
+    }
+
+    #[test_log::test]
+    fn test_identify_document() {
+        let mut identifier = Identifier::new(shared_model());
+        let doc = "The weather is nice today. I will go for a walk. Hola, ¿qué tal?";
+        let pred = identifier.identify_document(doc, sentence_splitter_default);
+        assert_eq!(pred.0, Lang::eng);
+
+        // Undetermined sentences do not outvote the others
+        identifier.set_min_text_length(10);
+        let doc = "Hi. Ok. Yes. The weather is nice today.";
+        let pred = identifier.identify_document(doc, sentence_splitter_default);
+        assert_eq!(pred.0, Lang::eng);
+        let pred = identifier.identify_document("Hi. Ok. 42.", sentence_splitter_default);
+        assert_eq!(pred, (Lang::und, identifier.penalty));
+    }
+
+    #[test_log::test]
+    fn test_identify_paragraph_wise() {
+        let mut identifier = Identifier::new(shared_model());
+        let doc = "The weather is nice today.\r\n\r\n\n\nHola, ¿qué tal?\n\nBon dia, com estàs?";
+        let preds = identifier.identify_paragraph_wise(doc);
+        assert_eq!(
+            preds.iter().map(|p| p.0).collect::<Vec<_>>(),
+            vec![Lang::eng, Lang::spa, Lang::cat]
+        );
+    }
+
+    #[test_log::test]
+    fn test_annotate_words() {
+        let mut identifier = Identifier::new(shared_model());
+        let words = identifier.annotate_words("Hola , world");
+        assert_eq!(words.len(), 3);
+        assert_eq!(words[0].0, "Hola");
+        assert_eq!(words[1].1, Lang::zxx);
+    }
+
+    #[test_log::test]
+    fn test_sliding_window() {
+        let mut identifier = Identifier::new(shared_model());
+        let text = "The weather is nice today  Hola qué tal estás amigo";
+        let windows = identifier.identify_sliding_window(text, 5, 5);
+        assert_eq!(
+            windows,
+            vec![(0, Lang::eng, windows[0].2), (5, Lang::spa, windows[1].2)]
+        );
+        assert_eq!(identifier.identify_sliding_window(text, 4, 3).len(), 3);
+    }
+
+    #[test_log::test]
+    fn test_identify_with_fallback() {
+        let mut identifier = Identifier::new(shared_model());
+        assert_eq!(identifier.identify("Hola", false).0, Lang::und);
+        let (lang, _, met) = identifier.identify_with_fallback("Hola");
+        assert_ne!(lang, Lang::und);
+        assert!(!met);
+        assert_eq!(
+            identifier.identify_with_fallback("Hola, ¿qué tal?"),
+            (
+                Lang::spa,
+                identifier.identify("Hola, ¿qué tal?", false).1,
+                true
+            )
+        );
+    }
+
+    #[test_log::test]
+    fn test_identify_min_confidence() {
+        let mut identifier = Identifier::new(shared_model());
+        assert_ne!(identifier.identify_min_confidence("Hola", 0.0).0, Lang::und);
+        assert_eq!(
+            identifier
+                .identify_min_confidence("Hola, ¿qué tal?", 100.0)
+                .0,
+            Lang::und
+        );
+    }
+
+    #[test_log::test]
+    fn test_set_confidence_for() {
+        let mut identifier = Identifier::new(shared_model());
+        identifier.set_confidence_for(Lang::spa, 100.0);
+        assert_eq!(identifier.get_confidence(Lang::spa), 100.0);
+        assert_eq!(identifier.get_confidence_all()[&Lang::spa], 100.0);
+        assert_eq!(identifier.identify("Hola, ¿qué tal?", false).0, Lang::und);
+    }
+
+    const LABELED: [(&str, Lang); 3] = [
+        ("Hola, ¿qué tal?", Lang::spa),
+        ("¿Dónde está la estación de tren?", Lang::spa),
+        ("Bon dia, com estàs?", Lang::cat),
+    ];
+
+    #[test_log::test]
+    fn test_calibrate() {
+        let mut identifier = Identifier::new(shared_model());
+        let labeled = LABELED.map(|(text, lang)| (text.to_string(), lang));
+        identifier.set_confidence_for(Lang::spa, 100.0);
+        assert!(identifier.calibrate(&labeled, 1.5).is_err());
+        identifier.calibrate(&labeled, 1.0).unwrap();
+        assert!(identifier.get_confidence(Lang::spa) < 100.0);
+        assert_eq!(identifier.identify("Hola, ¿qué tal?", false).0, Lang::spa);
+    }
+
+    #[test_log::test]
+    fn test_calibrate_recall() {
+        let mut identifier = Identifier::new(shared_model());
+        let labeled = LABELED.map(|(text, lang)| (text.to_string(), lang));
+        identifier.set_confidence_for(Lang::spa, 100.0);
+        assert!(identifier.calibrate_recall(&labeled, 0.0).is_err());
+        identifier.calibrate_recall(&labeled, 1.0).unwrap();
+        for (text, lang) in LABELED.iter().take(2) {
+            assert_eq!(identifier.identify(text, false).0, *lang);
+        }
+    }
+
+    #[test_log::test]
+    fn test_save_thresholds() {
+        let mut identifier = Identifier::new(shared_model());
+        identifier.set_confidence_for(Lang::spa, 1.5);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(Model::CONFIDENCE_FILE);
+        identifier.save_thresholds(&path).unwrap();
+        let thresholds = Model::load_confidence(&path, true).unwrap();
+        assert_eq!(thresholds.get(Lang::spa), 1.5);
+        assert_eq!(
+            thresholds.get(Lang::cat),
+            identifier.get_confidence(Lang::cat)
+        );
+    }
+
+    #[test_log::test]
+    fn test_ngram_coverage() {
+        let mut identifier = Identifier::new(shared_model());
+        assert_eq!(identifier.ngram_coverage("Hola, ¿qué tal?"), 1.0);
+        assert_eq!(identifier.ngram_coverage("Hola 𐌰𐌱𐌲"), 0.5);
+        assert_eq!(identifier.ngram_coverage(""), 0.0);
+    }
+
+    #[test_log::test]
+    fn test_unicode_normalization() {
+        let mut identifier = Identifier::new(shared_model());
+        // "sí" with a combining accent
+        let nfd = "Hola, ¿qué tal? Si\u{0301}";
+        identifier.set_unicode_normalization(NormalizationForm::Nfc);
+        assert_eq!(identifier.ngram_coverage(nfd), 1.0);
+    }
+
+    #[test_log::test]
+    fn test_lazy_model() {
+        Python::initialize();
+        let model = Arc::new(
+            LazyModel::load(
+                &python::module_path().expect("Python module needs to be installed"),
+                true,
+            )
+            .expect("Could not load model, please run 'heliport bianrize' if you haven't"),
+        );
+        let mut identifier = Identifier::new(model.clone());
+        assert!(!model.is_loaded(OrderNgram::Word));
+        assert_eq!(identifier.identify("Hola, ¿qué tal?", false).0, Lang::spa);
+        assert!(model.is_loaded(OrderNgram::Word));
+    }
+
+    #[test_log::test]
+    fn test_strip_urls() {
+        Python::initialize();
+        let mut identifier = Identifier::load(
+            &python::module_path().expect("Python module needs to be installed"),
+            None,
+            None,
+        )
+        .expect("Could not load model, please run 'heliport bianrize' if you haven't");
+        let text = "Link: https://www.example.com/news/articles/latest-updates.html";
+        assert_ne!(identifier.identify(text, false).0, Lang::und);
+        identifier.set_strip_urls(true);
+        assert_eq!(identifier.identify(text, false).0, Lang::und);
+    }
+
+    #[test_log::test]
+    fn test_non_linguistic() {
+        Python::initialize();
+        let mut identifier = Identifier::load(
+            &python::module_path().expect("Python module needs to be installed"),
+            None,
+            None,
+        )
+        .expect("Could not load model, please run 'heliport bianrize' if you haven't");
+        assert_eq!(
+            identifier.identify("123, 456.78 -- (90)!", false),
+            (Lang::zxx, 0.0)
+        );
+        assert_eq!(identifier.identify("", true), (Lang::zxx, 0.0));
+        // Minimum length only applies to texts with words
+        identifier.set_min_text_length(10);
+        assert_eq!(
+            identifier.identify("123, 456.78 -- (90)!", false),
+            (Lang::zxx, 0.0)
+        );
+        assert_eq!(identifier.identify("Hola", false).0, Lang::und);
+    }
+
+    #[test_log::test]
+    fn test_script_filter() {
+        Python::initialize();
+        let mut identifier = Identifier::load(
+            &python::module_path().expect("Python module needs to be installed"),
+            None,
+            None,
+        )
+        .expect("Could not load model, please run 'heliport bianrize' if you haven't");
+        identifier.set_script_filter(true);
+        for (text, expected) in INPUT_SENTS.iter().zip(EXPECTED_PREDS).take(12) {
+            assert_eq!(identifier.identify(text, false).0, expected.0);
+        }
+        // Without competing latin languages, the @K does not lower the confidence
+        assert_eq!(identifier.identify(INPUT_SENTS[12], false).0, Lang::guj);
+        let scores = identifier.score_all(INPUT_SENTS[5]).unwrap();
+        assert_eq!(scores.get(Lang::eng), 7.0);
+
+        let text = "日本語の文章を書きました";
+        assert_eq!(identifier.identify(text, true).0, Lang::jpn);
+        identifier.set_split_kana(false);
+        assert_eq!(identifier.identify(text, true).0, Lang::jpn);
+    }
+
+    #[test]
+    fn test_par_identify_config() {
+        Python::initialize();
+        let modelpath = python::module_path().expect("Python module needs to be installed");
+        let model = Arc::new(
+            Model::load(&modelpath, true, false, None)
+                .expect("Could not load model, please run 'heliport bianrize' if you haven't"),
+        );
+        let texts = || INPUT_SENTS.map(String::from).to_vec();
+        let identifier = Identifier::builder().model(model.clone()).build().unwrap();
+        let preds = identifier.par_identify(texts(), true);
+        assert_eq!(preds[0].0, EXPECTED_PREDS[0].0);
+
+        // Each identifier has to use its own settings in the parallel workers
+        let mut restricted = Identifier::builder()
+            .model(model)
+            .language_restriction(&[Lang::zul])
+            .build()
+            .unwrap();
+        let preds = restricted.par_identify(texts(), true);
+        for (text, pred) in INPUT_SENTS.iter().zip(preds) {
+            assert_eq!(restricted.identify(text, true), pred);
+        }
+        assert_ne!(
+            restricted.identify(INPUT_SENTS[0], true).0,
+            EXPECTED_PREDS[0].0
+        );
+    }
 }
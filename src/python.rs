@@ -1,4 +1,7 @@
+use std::collections::HashMap;
 use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::{Arc, LazyLock};
@@ -7,6 +10,9 @@ use std::{error::Error, fmt};
 use anyhow::Context;
 use pyo3::exceptions::PyOSError;
 use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyCFunction, PyString};
+use rayon::prelude::*;
+use strum::{EnumCount, IntoEnumIterator};
 
 #[cfg(feature = "cli")]
 use crate::cli::cli_run;
@@ -36,6 +42,21 @@ pub fn py_cli_run() -> PyResult<()> {
     Ok(())
 }
 
+/// List the supported languages.
+///
+/// With `in_model=True` only the languages present in the installed model are listed.
+#[pyfunction]
+#[pyo3(name = "list_languages", signature = (in_model=false))]
+pub fn py_list_languages(in_model: bool) -> PyResult<Vec<PyLang>> {
+    let mut langs: Vec<Lang> = if in_model {
+        get_model_instance()?.languages().into_iter().collect()
+    } else {
+        Lang::iter().collect()
+    };
+    langs.sort_by_key(|lang| *lang as usize);
+    Ok(langs.into_iter().map(PyLang::from).collect())
+}
+
 // Custom Error type to handle different types of model loading errors
 #[derive(Debug, Clone)]
 enum LoadModelError {
@@ -97,13 +118,28 @@ impl Identifier {
         Ok(identifier)
     }
 
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Nothing to release for now, exceptions are never suppressed
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &self,
+        _exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_value: Option<&Bound<'_, PyAny>>,
+        _traceback: Option<&Bound<'_, PyAny>>,
+    ) -> bool {
+        false
+    }
+
     /// Identify the language of a string.
     ///
     /// If confidence threshold is enabled (default), all predictions below
     /// the threshold will be labeled as 'und'.
     #[pyo3(name = "identify", signature = (text, ignore_confidence=false))]
-    fn py_identify(&mut self, text: &str, ignore_confidence: bool) -> String {
-        self.identify(text, ignore_confidence).0.to_string()
+    fn py_identify(&mut self, text: &str, ignore_confidence: bool) -> PyLang {
+        self.identify(text, ignore_confidence).0.into()
     }
 
     /// Identify the language of a string and return the prediction score.
@@ -113,37 +149,37 @@ impl Identifier {
     /// value (higher is better) if the threshold is enabled, and the
     /// raw score if the threshold is disabled.
     #[pyo3(name = "identify_with_score", signature = (text, ignore_confidence=false))]
-    fn py_identify_with_score(&mut self, text: &str, ignore_confidence: bool) -> (String, f32) {
+    fn py_identify_with_score(&mut self, text: &str, ignore_confidence: bool) -> (PyLang, f32) {
         let pred = self.identify(text, ignore_confidence);
-        (pred.0.to_string(), pred.1)
+        (pred.0.into(), pred.1)
     }
 
     /// Identify the top-k languages of a string.
     #[pyo3(name = "identify_topk")]
-    fn py_identify_topk(&mut self, text: &str, k: usize) -> Vec<String> {
+    fn py_identify_topk(&mut self, text: &str, k: usize) -> Vec<PyLang> {
         self.identify_topk(text, k)
             .iter()
-            .map(|(pred, _)| pred.to_string())
+            .map(|(pred, _)| (*pred).into())
             .collect()
     }
 
     /// Identify the top-k languages of a string and return each language raw score.
     #[pyo3(name = "identify_topk_with_score")]
-    fn py_identify_topk_with_score(&mut self, text: &str, k: usize) -> Vec<(String, f32)> {
+    fn py_identify_topk_with_score(&mut self, text: &str, k: usize) -> Vec<(PyLang, f32)> {
         self.identify_topk(text, k)
             .iter()
-            .map(|(pred, conf)| (pred.to_string(), conf.clone()))
+            .map(|(pred, conf)| ((*pred).into(), *conf))
             .collect()
     }
 
     /// Parallelized version of `identify`, which takes a list of strings
     /// and runs the identification in parallel.
     #[pyo3(name = "par_identify", signature = (texts, ignore_confidence=false))]
-    fn py_par_identify(&mut self, texts: Vec<String>, ignore_confidence: bool) -> Vec<String> {
+    fn py_par_identify(&mut self, texts: Vec<String>, ignore_confidence: bool) -> Vec<PyLang> {
         let preds = self.par_identify(texts, ignore_confidence);
         let mut preds_out = Vec::with_capacity(preds.len());
         for pred in preds {
-            preds_out.push(pred.0.to_string());
+            preds_out.push(pred.0.into());
         }
         preds_out
     }
@@ -155,40 +191,278 @@ impl Identifier {
         &mut self,
         texts: Vec<String>,
         ignore_confidence: bool,
-    ) -> Vec<(String, f32)> {
+    ) -> Vec<(PyLang, f32)> {
         let preds = self.par_identify(texts, ignore_confidence);
         let mut preds_out = Vec::with_capacity(preds.len());
         for pred in preds {
-            preds_out.push((pred.0.to_string(), pred.1));
+            preds_out.push((pred.0.into(), pred.1));
         }
         preds_out
     }
 
+    /// Parallelized version of `identify_topk`, which takes a list of strings
+    /// and runs the identification in parallel.
+    #[pyo3(name = "par_identify_topk")]
+    fn py_par_identify_topk(&mut self, texts: Vec<String>, k: usize) -> Vec<Vec<PyLang>> {
+        self.par_identify_topk(texts, k)
+            .into_iter()
+            .map(|preds| preds.iter().map(|(pred, _)| (*pred).into()).collect())
+            .collect()
+    }
+
+    /// Parallelized version of `identify_topk_with_score`, which takes a list of strings
+    /// and runs the identification in parallel.
+    #[pyo3(name = "par_identify_topk_with_score")]
+    fn py_par_identify_topk_with_score(
+        &mut self,
+        texts: Vec<String>,
+        k: usize,
+    ) -> Vec<Vec<(PyLang, f32)>> {
+        self.par_identify_topk(texts, k)
+            .into_iter()
+            .map(|preds| {
+                preds
+                    .iter()
+                    .map(|(pred, score)| ((*pred).into(), *score))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Obtain the raw scores of all the languages for a string, lower is better.
+    ///
+    /// Returns an empty dict if there is nothing to score.
+    #[pyo3(name = "get_all_scores")]
+    fn py_get_all_scores(&mut self, text: &str) -> HashMap<PyLang, f32> {
+        match self.score_all(text) {
+            Some(scores) => scores
+                .to_map()
+                .into_iter()
+                .map(|(lang, score)| (lang.into(), score))
+                .collect(),
+            None => HashMap::new(),
+        }
+    }
+
+    /// Obtain the raw scores of all the languages for a string as a numpy array.
+    ///
+    /// The array is indexed by the position of each language, that can be obtained
+    /// with `int()` or using the `Lang` object directly as index.
+    /// All values are NaN if there is nothing to score.
+    /// Raises `ImportError` if numpy is not installed.
+    #[pyo3(name = "score_array")]
+    fn py_score_array<'py>(&mut self, py: Python<'py>, text: &str) -> PyResult<Bound<'py, PyAny>> {
+        let numpy = py.import("numpy")?;
+        let bytes: Vec<u8> = match self.score_all(text) {
+            Some(scores) => scores
+                .iter()
+                .flat_map(|(_, score)| score.to_ne_bytes())
+                .collect(),
+            None => f32::NAN.to_ne_bytes().repeat(Lang::COUNT),
+        };
+        // frombuffer returns a read-only view of the bytes, copy to own the data
+        numpy
+            .call_method1("frombuffer", (PyBytes::new(py, &bytes), "float32"))?
+            .call_method0("copy")
+    }
+
+    /// Identify the language of each line in a file.
+    ///
+    /// The file is read on the Rust side. If `num_threads` is greater than 0,
+    /// lines are identified in parallel with that number of threads.
+    #[pyo3(name = "identify_file", signature = (path, num_threads=0, ignore_confidence=false))]
+    fn py_identify_file(
+        &mut self,
+        py: Python<'_>,
+        path: PathBuf,
+        num_threads: usize,
+        ignore_confidence: bool,
+    ) -> PyResult<Vec<PyLang>> {
+        let reader = BufReader::new(File::open(&path)?);
+        let lines = reader.lines().collect::<io::Result<Vec<String>>>()?;
+        let preds = py.detach(|| -> PyResult<Vec<(Lang, f32)>> {
+            if num_threads == 0 {
+                return Ok(lines
+                    .iter()
+                    .map(|line| self.identify(line, ignore_confidence))
+                    .collect());
+            }
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .context("Could not create the thread pool")?;
+            Ok(pool.install(|| self.par_identify(lines, ignore_confidence)))
+        })?;
+        Ok(preds.into_iter().map(|(lang, _)| lang.into()).collect())
+    }
+
+    /// Asynchronous version of `identify`, to be awaited from an asyncio event loop.
+    ///
+    /// The identification runs in the default executor of the running loop
+    /// without holding the GIL, so it does not block other coroutines.
+    #[pyo3(name = "async_identify", signature = (text, ignore_confidence=false))]
+    fn py_async_identify<'py>(
+        &self,
+        py: Python<'py>,
+        text: String,
+        ignore_confidence: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let identifier = self.clone();
+        let func = PyCFunction::new_closure(py, None, None, move |args, _| -> PyLang {
+            let mut identifier = identifier.clone();
+            args.py()
+                .detach(|| identifier.identify(&text, ignore_confidence).0)
+                .into()
+        })?;
+        run_in_executor(py, func)
+    }
+
+    /// Asynchronous version of `par_identify`, to be awaited from an asyncio event loop.
+    #[pyo3(name = "async_par_identify", signature = (texts, ignore_confidence=false))]
+    fn py_async_par_identify<'py>(
+        &self,
+        py: Python<'py>,
+        texts: Vec<String>,
+        ignore_confidence: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let identifier = self.clone();
+        let func = PyCFunction::new_closure(py, None, None, move |args, _| -> Vec<PyLang> {
+            let preds = args.py().detach(|| {
+                identifier.par_identify_str(texts.par_iter().map(String::as_str), ignore_confidence)
+            });
+            preds.into_iter().map(|(lang, _)| lang.into()).collect()
+        })?;
+        run_in_executor(py, func)
+    }
+
     /// Obtain confidence threshold for a language
     #[pyo3(name = "get_confidence")]
-    fn py_get_confidence(&self, lang_str: &str) -> PyResult<f32> {
-        let lang = Lang::from_str(lang_str).context("Language code does not exist")?;
-        Ok(self.get_confidence(lang))
+    fn py_get_confidence(&self, lang: &Bound<'_, PyAny>) -> PyResult<f32> {
+        Ok(self.get_confidence(extract_lang(lang)?))
     }
 
     /// Obtain all confidence thresholds
     #[pyo3(name = "get_confidence_all")]
-    fn py_get_confidence_all(&self) -> std::collections::HashMap<String,f32> {
-        self.get_confidence_all_string()
+    fn py_get_confidence_all(&self) -> HashMap<PyLang, f32> {
+        self.get_confidence_all()
+            .into_iter()
+            .map(|(lang, threshold)| (lang.into(), threshold))
+            .collect()
     }
 }
 
-// #[pyclass(name = "Lang")]
-// pub struct PyLang {
-//     inner: Lang,
-// }
+// Schedule a blocking call in the default executor of the running asyncio loop
+// returns a future that can be awaited
+fn run_in_executor<'py>(
+    py: Python<'py>,
+    func: Bound<'py, PyCFunction>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    event_loop.call_method1("run_in_executor", (py.None(), func))
+}
+
+// Accept either a Lang object or a language code string
+fn extract_lang(obj: &Bound<'_, PyAny>) -> PyResult<Lang> {
+    if let Ok(lang) = obj.downcast::<PyLang>() {
+        return Ok(lang.get().inner);
+    }
+    let code: &str = obj.extract()?;
+    Ok(Lang::from_str(code).context("Language code does not exist")?)
+}
+
+/// Language returned by the identifier.
+///
+/// Converts to its language code with `str()` and compares equal to it,
+/// so `lang == 'eng'` keeps working as with plain strings.
+#[pyclass(name = "Lang", frozen)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PyLang {
+    inner: Lang,
+}
+
+impl From<Lang> for PyLang {
+    fn from(inner: Lang) -> Self {
+        Self { inner }
+    }
+}
+
+#[pymethods]
+impl PyLang {
+    #[new]
+    fn py_new(code: &str) -> PyResult<Self> {
+        let inner = Lang::from_str(code).context("Language code does not exist")?;
+        Ok(Self { inner })
+    }
+
+    /// Language code, as used in the model files
+    #[getter]
+    fn code(&self) -> String {
+        self.inner.to_string()
+    }
+
+    /// Name of the language in English
+    #[getter]
+    fn human_name(&self) -> &'static str {
+        self.inner.human_name()
+    }
+
+    /// ISO 15924 script code
+    #[getter]
+    fn script(&self) -> &'static str {
+        self.inner.script()
+    }
+
+    /// ISO 639-3 language code, without the script
+    #[getter]
+    fn language_subtag(&self) -> &'static str {
+        self.inner.language_subtag()
+    }
+
+    /// Whether the script is written right to left
+    #[getter]
+    fn is_rtl(&self) -> bool {
+        self.inner.is_rtl()
+    }
+
+    /// Whether the language is Chinese, Japanese or Korean
+    #[getter]
+    fn is_cjk(&self) -> bool {
+        self.inner.is_cjk()
+    }
+
+    fn __index__(&self) -> usize {
+        self.inner as usize
+    }
+
+    fn __str__(&self) -> String {
+        self.inner.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Lang('{}')", self.inner)
+    }
+
+    fn __eq__(&self, other: &Bound<'_, PyAny>) -> bool {
+        extract_lang(other).is_ok_and(|lang| lang == self.inner)
+    }
+
+    fn __ne__(&self, other: &Bound<'_, PyAny>) -> bool {
+        !self.__eq__(other)
+    }
+
+    // Same hash as the code string, so they can be used interchangeably as dict keys
+    fn __hash__(&self, py: Python<'_>) -> PyResult<isize> {
+        PyString::new(py, &self.inner.to_string()).hash()
+    }
+}
 
 #[pymodule]
 fn heliport(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     #[cfg(feature = "cli")]
     m.add_wrapped(wrap_pyfunction!(py_cli_run))?;
+    m.add_wrapped(wrap_pyfunction!(py_list_languages))?;
     m.add_class::<Identifier>()?;
-    // m.add_class::<PyLang>()?;
+    m.add_class::<PyLang>()?;
 
     Ok(())
 }
@@ -1,9 +1,10 @@
-use std::fs::File;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
 use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
 use std::str::FromStr;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use counter::Counter;
 use lazy_static::lazy_static;
 use log::{debug, info, warn};
@@ -21,14 +22,116 @@ lazy_static! {
         Regex::new(r"(\w{3,7}).train$").expect("Error compiling lang name from file regex");
 }
 
+/// Recommended minimum number of lines of a training file
+pub const MIN_TRAINING_LINES: usize = 1000;
+
+/// Summary of a training file checked by [`validate_training_file`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationReport {
+    pub lang: Lang,
+    pub lines: usize,
+    pub chars: usize,
+    /// Ratio of ASCII letters among all the letters
+    pub ascii_ratio: f64,
+    /// Problems found that do not prevent training
+    pub warnings: Vec<String>,
+}
+
+// Obtain the language code from the training file name
+fn lang_from_filename(input_file_path: &Path) -> Result<String> {
+    let string_file_name = input_file_path.to_string_lossy();
+    Ok(RE_LANG_NAME
+        .captures(&string_file_name)
+        .context("Could not parse language name from input_file")?
+        .get(1)
+        .with_context(|| "Could not get first capture group from lang name regex")?
+        .as_str()
+        .to_string())
+}
+
+/// Check that a training file can be used to train a language
+///
+/// Fails if the file is not valid UTF-8, is empty or the language code in the name does not exist.
+/// Files with less than `min_lines` lines or non-Latin script languages
+/// with more than 95% of ASCII letters are reported as warnings.
+pub fn validate_training_file(path: &Path, min_lines: usize) -> Result<ValidationReport> {
+    let lang_string = lang_from_filename(path)?;
+    let lang = Lang::from_str(&lang_string)
+        .with_context(|| format!("Language code '{lang_string}' does not exist"))?;
+
+    let mut reader = BufReader::new(
+        File::open(path).with_context(|| format!("Could not open '{}'", path.display()))?,
+    );
+    let mut buf = Vec::new();
+    let mut lines = 0;
+    let mut chars = 0;
+    let mut letters = 0;
+    let mut ascii_letters = 0;
+    while reader.read_until(b'\n', &mut buf)? > 0 {
+        lines += 1;
+        // fail early, invalid bytes would make the whole training fail
+        let line =
+            std::str::from_utf8(&buf).with_context(|| format!("Invalid UTF-8 in line {lines}"))?;
+        for c in line.trim_end_matches('\n').chars() {
+            chars += 1;
+            if c.is_alphabetic() {
+                letters += 1;
+                if c.is_ascii() {
+                    ascii_letters += 1;
+                }
+            }
+        }
+        buf.clear();
+    }
+    if chars == 0 {
+        bail!("File is empty");
+    }
+
+    let ascii_ratio = if letters > 0 {
+        ascii_letters as f64 / letters as f64
+    } else {
+        0.0
+    };
+    let mut warnings = Vec::new();
+    if lines < min_lines {
+        warnings.push(format!(
+            "File has only {lines} lines, at least {min_lines} are recommended"
+        ));
+    }
+    if lang.script() != "Latn" && ascii_ratio > 0.95 {
+        warnings.push(format!(
+            "{:.1}% of the letters are ASCII, but '{lang}' is written in {} script",
+            ascii_ratio * 100.0,
+            lang.script()
+        ));
+    }
+
+    Ok(ValidationReport {
+        lang,
+        lines,
+        chars,
+        ascii_ratio,
+        warnings,
+    })
+}
+
 // Count n-gram frequency of a given n-gram order in the text contained in the file
-fn count_ngrams(input_file_path: &Path, order: OrderNgram) -> Result<Counter<String>> {
+// n-grams with frequency lower than min_frequency are discarded
+fn count_ngrams(
+    input_file_path: &Path,
+    order: OrderNgram,
+    min_frequency: u64,
+) -> Result<Counter<String>> {
     let input_file = BufReader::new(File::open(input_file_path)?);
     let mut counts = Counter::new();
 
     // Read training file line by line and accumulate ngram counts
-    for line_res in input_file.lines() {
-        let line = line_res?;
+    for (i, line_res) in input_file.lines().enumerate() {
+        let mut line = line_res?;
+        // Remove byte order mark, Windows editors may add it
+        if i == 0 && line.starts_with('\u{FEFF}') {
+            line.drain(..'\u{FEFF}'.len_utf8());
+        }
         // Replace punctuation by spaces
         let replaced = RE_NON_ALPHA.replace_all(&line, " ");
 
@@ -58,20 +161,34 @@ fn count_ngrams(input_file_path: &Path, order: OrderNgram) -> Result<Counter<Str
             }
         }
     }
+    // Remove rare n-grams, so they are not counted in the total
+    counts.retain(|_, count| *count as u64 >= min_frequency);
 
     Ok(counts)
 }
 
+// Whether the output file exists and has been modified after the input file
+fn is_up_to_date(output_file_path: &Path, input_file_path: &Path) -> Result<bool> {
+    let Ok(output_meta) = fs::metadata(output_file_path) else {
+        return Ok(false);
+    };
+    let input_modified = fs::metadata(input_file_path)
+        .and_then(|m| m.modified())
+        .with_context(|| format!("Could not read '{}'", input_file_path.display()))?;
+    Ok(output_meta.modified()? > input_modified)
+}
+
 // Count n-gram frequency of all n-gram orders for a given lanuage
-pub fn count_all_ngrams(input_file_path: &Path, output_dir: &Path, top_k: usize) -> Result<()> {
+// If incremental, orders with an up to date model file are not generated again
+pub fn count_all_ngrams(
+    input_file_path: &Path,
+    output_dir: &Path,
+    top_k: usize,
+    min_frequency: u64,
+    incremental: bool,
+) -> Result<()> {
     // use the lang prefix in the input file as language code
-    let string_file_name = input_file_path.to_string_lossy();
-    let lang_string = RE_LANG_NAME
-        .captures(&string_file_name)
-        .context("Could not parse language name from input_file")?
-        .get(1)
-        .with_context(|| "Could not get first capture group from lang name regex")?
-        .as_str();
+    let lang_string = lang_from_filename(input_file_path)?;
     // Check that the language exists
     // warn if does not exist
     if Lang::from_str(&lang_string).is_err() {
@@ -84,15 +201,16 @@ pub fn count_all_ngrams(input_file_path: &Path, output_dir: &Path, top_k: usize)
     let results: Vec<Result<_>> = ngram_orders
         .into_par_iter()
         .map(|order| -> Result<()> {
+            // output file with the language code and ngram order as name
+            let output_path = output_dir.join(format!("{lang_string}.{order}.model"));
+            if incremental && is_up_to_date(&output_path, input_file_path)? {
+                debug!("Skipping up to date '{}'", output_path.display());
+                return Ok(());
+            }
             // Obtain nggram frequencies
-            let counts = count_ngrams(input_file_path, order)?;
-            // create output file with the language code and ngram order as name
-            let output_file = File::create(output_dir.join(format!(
-                "{}.{}.model",
-                lang_string,
-                order.to_string()
-            )))
-            .with_context(|| "Could not create file")?;
+            let counts = count_ngrams(input_file_path, order, min_frequency)?;
+            let output_file =
+                File::create(&output_path).with_context(|| "Could not create file")?;
             let mut output_file = BufWriter::new(output_file);
             let total = counts.total::<usize>();
             debug!(
@@ -117,3 +235,86 @@ pub fn count_all_ngrams(input_file_path: &Path, output_dir: &Path, top_k: usize)
     info!("Finished '{lang_string}'");
     Ok(())
 }
+
+// Write the languagelist file needed to load a plain text model
+// with the languages that have model files of all the n-gram orders in the input dir
+pub fn generate_languagelist(input_dir: &Path, output_dir: &Path) -> Result<()> {
+    // Collect the n-gram orders found for each language code
+    let mut found: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    for entry in fs::read_dir(input_dir)
+        .with_context(|| format!("Could not read directory '{}'", input_dir.display()))?
+    {
+        let file_name = entry?.file_name();
+        let file_name = file_name.to_string_lossy();
+        // model files are named {lang}.{order}.model
+        let Some(stem) = file_name.strip_suffix(".model") else {
+            continue;
+        };
+        let Some((lang, order)) = stem.split_once('.') else {
+            continue;
+        };
+        found
+            .entry(lang.to_string())
+            .or_default()
+            .insert(order.to_string());
+    }
+
+    let mut langs = Vec::with_capacity(found.len());
+    for (lang_string, orders) in found {
+        if Lang::from_str(&lang_string).is_err() {
+            warn!("Language code '{lang_string}' does not exist, omitting from languagelist");
+            continue;
+        }
+        let missing: Vec<_> = OrderNgram::iter()
+            .map(|order| order.to_string())
+            .filter(|order| !orders.contains(order))
+            .collect();
+        if !missing.is_empty() {
+            warn!("Language '{lang_string}' is missing {missing:?} models, omitting from languagelist");
+            continue;
+        }
+        langs.push(lang_string);
+    }
+
+    let output_path = output_dir.join("languagelist");
+    let mut output_file = BufWriter::new(
+        File::create(&output_path)
+            .with_context(|| format!("Could not create file '{}'", output_path.display()))?,
+    );
+    for lang in &langs {
+        writeln!(&mut output_file, "{lang}")?;
+    }
+    output_file.flush()?;
+    info!(
+        "Written {} languages to '{}'",
+        langs.len(),
+        output_path.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_count_ngrams_bom() {
+        let text = "the water is clear\nthe sky\n";
+        let plain = NamedTempFile::new().unwrap();
+        fs::write(plain.path(), text).unwrap();
+        let bom = NamedTempFile::new().unwrap();
+        fs::write(bom.path(), format!("\u{FEFF}{text}")).unwrap();
+
+        for order in OrderNgram::iter() {
+            let expected = count_ngrams(plain.path(), order, 1).unwrap();
+            let counts = count_ngrams(bom.path(), order, 1).unwrap();
+            assert_eq!(
+                counts.most_common_ordered(),
+                expected.most_common_ordered(),
+                "{order}"
+            );
+        }
+    }
+}
@@ -8,6 +8,10 @@ use unicode_blocks;
 lazy_static! {
     pub static ref RE_NON_ALPHA: Regex = Regex::new(r#"[^#gc\p{L}\p{M}′'’´ʹािीुूृेैोौंँः् া ি ী ু ূ ৃ ে ৈ ো ৌ।্্্я̄\u07A6\u07A7\u07A8\u07A9\u07AA\u07AB\u07AC\u07AD\u07AE\u07AF\u07B0\u0A81\u0A82\u0A83\u0ABC\u0ABD\u0ABE\u0ABF\u0AC0\u0AC1\u0AC2\u0AC3\u0AC4\u0AC5\u0AC6\u0AC7\u0AC8\u0AC9\u0ACA\u0ACB\u0ACC\u0ACD\u0AD0\u0AE0\u0AE1\u0AE2\u0AE3\u0AE4\u0AE5\u0AE6\u0AE7\u0AE8\u0AE9\u0AEA\u0AEB\u0AEC\u0AED\u0AEE\u0AEF\u0AF0\u0AF1]"#)
             .expect("Error compiling non-alpha regex for Idenfifier");
+    pub static ref RE_URL: Regex = Regex::new(
+        r"(?i)\b(?:https?://|ftp://|www\.)\S+|[\w.+-]+@[\w-]+(?:\.[\w-]+)+"
+    )
+    .expect("Error compiling URL regex for Identifier");
 }
 
 // Trait that extracts the contained ok value or aborts if error
@@ -69,3 +73,179 @@ pub fn is_cjk_block(c: char) -> Result<bool, ()> {
     }
     Ok(false)
 }
+
+const KANA_BLOCKS: [unicode_blocks::UnicodeBlock; 7] = [
+    unicode_blocks::HIRAGANA,
+    unicode_blocks::KATAKANA,
+    unicode_blocks::KATAKANA_PHONETIC_EXTENSIONS,
+    unicode_blocks::KANA_SUPPLEMENT,
+    unicode_blocks::KANA_EXTENDED_A,
+    unicode_blocks::KANA_EXTENDED_B,
+    unicode_blocks::SMALL_KANA_EXTENSION,
+];
+
+/// Return if char belongs to Hiragana or Katakana unicode blocks
+pub fn is_kana_block(c: char) -> bool {
+    match unicode_blocks::find_unicode_block(c) {
+        Some(charset) => KANA_BLOCKS.contains(&charset),
+        None => false,
+    }
+}
+
+/// ISO 15924 code of the script a unicode block belongs to,
+/// only for the scripts of the languages supported
+fn block_script(block: unicode_blocks::UnicodeBlock) -> Option<&'static str> {
+    let name = block.name();
+    // Blocks whose name starts with the script name, and their extensions
+    const PREFIXES: [(&str, &str); 33] = [
+        ("Basic Latin", "Latn"),
+        ("Latin", "Latn"),
+        ("IPA Extensions", "Latn"),
+        ("Cyrillic", "Cyrl"),
+        ("Greek", "Grek"),
+        ("Arabic", "Arab"),
+        ("Hebrew", "Hebr"),
+        ("Armenian", "Armn"),
+        ("Georgian", "Geor"),
+        ("Devanagari", "Deva"),
+        ("Bengali", "Beng"),
+        ("Gurmukhi", "Guru"),
+        ("Gujarati", "Gujr"),
+        ("Oriya", "Orya"),
+        ("Tamil", "Taml"),
+        ("Telugu", "Telu"),
+        ("Kannada", "Knda"),
+        ("Malayalam", "Mlym"),
+        ("Sinhala", "Sinh"),
+        ("Thai", "Thai"),
+        ("Lao", "Laoo"),
+        ("Tibetan", "Tibt"),
+        ("Myanmar", "Mymr"),
+        ("Ethiopic", "Ethi"),
+        ("Khmer", "Khmr"),
+        ("Thaana", "Thaa"),
+        ("Syriac", "Syrc"),
+        ("Ol Chiki", "Olck"),
+        ("Cherokee", "Cher"),
+        ("Unified Canadian Aboriginal Syllabics", "Cans"),
+        ("Hangul", "Hang"),
+        ("CJK Unified Ideographs", "Hani"),
+        ("CJK Compatibility Ideographs", "Hani"),
+    ];
+    if name.starts_with("Hiragana") || name.starts_with("Katakana") || name.contains("Kana") {
+        return Some("Jpan");
+    }
+    PREFIXES
+        .iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map(|(_, script)| *script)
+}
+
+/// Detect the script (ISO 15924 code) of most of the alphabetic characters of a text
+///
+/// Hiragana and Katakana are reported as 'Jpan' and Han characters as 'Hani'.
+/// Returns `None` if there are no alphabetic characters of a known script.
+pub fn detect_predominant_script(text: &str) -> Option<&'static str> {
+    let mut counts: Vec<(&'static str, usize)> = Vec::new();
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        let Some(script) = unicode_blocks::find_unicode_block(c).and_then(block_script) else {
+            continue;
+        };
+        match counts.iter_mut().find(|(s, _)| *s == script) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((script, 1)),
+        }
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(script, _)| script)
+}
+
+/// Split a text into sentences at '.', '!' or '?'
+/// followed by whitespace and an uppercase letter
+pub fn sentence_splitter_default(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        if !matches!(c, '.' | '!' | '?') {
+            continue;
+        }
+        let end = i + c.len_utf8();
+        let rest = &text[end..];
+        let next = rest.trim_start();
+        if next.len() < rest.len() && next.starts_with(char::is_uppercase) {
+            sentences.push(text[start..end].trim());
+            start = text.len() - next.len();
+        }
+    }
+    let last = text[start..].trim();
+    if !last.is_empty() {
+        sentences.push(last);
+    }
+    sentences
+}
+
+/// Truncate a text to a maximum number of characters
+///
+/// The text is cut at the last whitespace before the limit,
+/// or at the limit itself if there is no whitespace (e.g. in CJK texts).
+pub fn truncate_at_word(text: &str, max_chars: usize) -> &str {
+    let Some((limit, next)) = text.char_indices().nth(max_chars) else {
+        return text;
+    };
+    let truncated = &text[..limit];
+    if next.is_whitespace() {
+        return truncated.trim_end();
+    }
+    match truncated.rfind(char::is_whitespace) {
+        Some(i) if !truncated[..i].trim_end().is_empty() => truncated[..i].trim_end(),
+        _ => truncated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sentence_splitter() {
+        assert_eq!(
+            sentence_splitter_default("Hola. Què tal? bé, i tu! Molt bé...  Adéu"),
+            vec!["Hola.", "Què tal? bé, i tu!", "Molt bé...", "Adéu"]
+        );
+        assert!(sentence_splitter_default("  ").is_empty());
+    }
+
+    #[test]
+    fn test_truncate_at_word() {
+        assert_eq!(truncate_at_word("Hola, què tal?", 20), "Hola, què tal?");
+        assert_eq!(truncate_at_word("Hola, què tal?", 12), "Hola, què");
+        assert_eq!(truncate_at_word("Hola, què tal?", 9), "Hola, què");
+        assert_eq!(truncate_at_word("Hola, què tal?", 3), "Hol");
+        assert_eq!(truncate_at_word("日本語の文章", 3), "日本語");
+    }
+
+    #[test]
+    fn test_predominant_script() {
+        assert_eq!(detect_predominant_script("Hola, què tal?"), Some("Latn"));
+        assert_eq!(
+            detect_predominant_script("Привет, как дела? ok"),
+            Some("Cyrl")
+        );
+        assert_eq!(detect_predominant_script("مرحبا بالعالم"), Some("Arab"));
+        assert_eq!(
+            detect_predominant_script("ひらがなとカタカナ"),
+            Some("Jpan")
+        );
+        assert_eq!(detect_predominant_script("123 !!"), None);
+    }
+
+    #[test]
+    fn test_kana() {
+        assert!(is_kana_block('の'));
+        assert!(is_kana_block('カ'));
+        assert!(!is_kana_block('語'));
+        assert!(!is_kana_block('a'));
+    }
+}
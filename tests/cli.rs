@@ -1,3 +1,5 @@
+use std::io::Read;
+
 use assert_cmd::Command;
 use predicates::prelude::*;
 
@@ -87,3 +89,839 @@ fn test_cli_identify_text_model() {
     .assert()
     .success();
 }
+
+#[test]
+fn test_cli_identify_json() {
+    // Each line should be a JSON object with lang, score and text
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "identify",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa",
+        "-c",
+        "--output-format",
+        "json",
+        "--include-text",
+    ])
+    .write_stdin("Hello, how are you?\n¿Qué tal estás?\n")
+    .assert()
+    .success()
+    .stdout(
+        predicate::str::is_match(
+            r#"^\{"lang":"eng","score":[0-9.]+,"text":"Hello, how are you\?"\}\n\{"lang":"spa","#,
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_cli_identify_ndjson() {
+    // Each line should be a JSON object with the index of the input line
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "identify",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa",
+        "-F",
+        "ndjson",
+        "-j",
+        "2",
+    ])
+    .write_stdin("Hello, how are you?\n¿Qué tal estás?\n")
+    .assert()
+    .success()
+    .stdout(
+        predicate::str::is_match(r#"^\{"index":0,"lang":"\w+","score":[0-9.]+\}\n\{"index":1,"#)
+            .unwrap(),
+    );
+}
+
+#[test]
+fn test_cli_identify_topk() {
+    // Each line should contain k language and score pairs
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "identify",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa,cat",
+        "--topk",
+        "2",
+    ])
+    .write_stdin("Hello, how are you?\n")
+    .assert()
+    .success()
+    .stdout(predicate::str::is_match(r"^eng\t[0-9.]+\t\w+\t[0-9.]+\n$").unwrap());
+}
+
+#[test]
+fn test_cli_eval() {
+    // Should print per-language metrics, accuracy and the most confused pairs
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "eval",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa",
+        "--top-confused",
+        "1",
+    ])
+    .write_stdin("Hello, how are you?\teng\n¿Qué tal estás?\tspa\n")
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("eng\t1.0000\t1.0000\t1.0000\t1\n"))
+    .stdout(predicate::str::contains("accuracy\t1.0000\n"))
+    .stdout(predicate::str::contains("gold\tpredicted\tcount\n"));
+}
+
+#[test]
+fn test_cli_eval_json() {
+    // Should print a JSON report without the languages under the minimum support
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    let output = cmd
+        .args([
+            "eval",
+            "--model-dir",
+            LANGUAGEMODELS,
+            "--relevant-langs",
+            "eng,spa",
+            "--output-format",
+            "json",
+            "--min-support",
+            "2",
+        ])
+        .write_stdin(
+            "Hello, how are you?\teng\nGood morning to everyone\teng\n¿Qué tal estás?\tspa\n",
+        )
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["accuracy"], 1.0);
+    assert_eq!(report["macro_f1"], 1.0);
+    assert_eq!(report["micro_f1"], 1.0);
+    let languages = report["languages"].as_array().unwrap();
+    assert_eq!(languages.len(), 1);
+    assert_eq!(languages[0]["lang"], "eng");
+    assert_eq!(languages[0]["support"], 2);
+}
+
+#[test]
+fn test_cli_compute_thresholds() {
+    // Should write a confidence thresholds file with all the model languages
+    let dir = tempfile::tempdir().unwrap();
+    let labeled = dir.path().join("labeled.tsv");
+    let output = dir.path().join("confidenceThresholds");
+    std::fs::write(
+        &labeled,
+        "Hello, how are you?\teng\nGood morning to everyone\teng\n¿Qué tal estás?\tspa\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "compute-thresholds",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa",
+        "--target-recall",
+        "1.0",
+        "--labeled",
+    ])
+    .arg(&labeled)
+    .arg("--output")
+    .arg(&output)
+    .assert()
+    .success();
+    let thresholds = std::fs::read_to_string(&output).unwrap();
+    assert!(thresholds.lines().any(|l| l.starts_with("eng\t")));
+    assert!(thresholds.lines().any(|l| l.starts_with("spa\t")));
+}
+
+#[test]
+fn test_cli_eval_invalid_label() {
+    // Should fail if a gold label is not a valid language code
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "eval",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa",
+    ])
+    .write_stdin("Hello, how are you?\tenglish\n")
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains(
+        "Language code 'english' at line 1",
+    ));
+}
+
+#[test]
+fn test_cli_list_langs() {
+    // Should list only the languages with the requested script
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args(["list-langs", "--filter-script", "Grek"])
+        .assert()
+        .success()
+        .stdout("code\tiso639-3\tscript\tname\nell\tell\tGrek\tGreek\n");
+}
+
+#[test]
+fn test_cli_list_langs_in_model() {
+    // Should list only languages with a model in the directory
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args(["list-langs", "--in-model", LANGUAGEMODELS, "-F", "json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""code":"cat""#))
+        .stdout(predicate::str::contains(r#""code":"zxx""#).not());
+}
+
+#[test]
+fn test_cli_info_missing_binary() {
+    // Should fail if the model directory does not have binarized models
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args(["info", "--model-dir", LANGUAGEMODELS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("word.bin' could not be found"));
+}
+
+#[test]
+fn test_cli_stats() {
+    // Should count each language, most frequent first
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "stats",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa",
+    ])
+    .write_stdin("Hello, how are you?\n¿Qué tal estás?\nGood morning to all of you\n\n")
+    .assert()
+    .success()
+    .stdout("eng\t2\t50.00\nspa\t1\t25.00\nzxx\t1\t25.00\n");
+}
+
+#[test]
+fn test_cli_stats_topk() {
+    // Soft counts of each text should add up to one
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "stats",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa",
+        "--topk",
+        "2",
+    ])
+    .write_stdin("Hello, how are you?\n\n")
+    .assert()
+    .success()
+    .stdout(
+        predicate::str::is_match(
+            r"^zxx\t1\.00\t50\.00\neng\t0\.9\d\t4\d\.\d\d\n\w+\t0\.0\d\t\d\.\d\d\n$",
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_cli_compare_models() {
+    // Comparing a model with itself should not find any difference
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "compare-models",
+        "--model-a",
+        LANGUAGEMODELS,
+        "--model-b",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa",
+    ])
+    .write_stdin("Hello, how are you?\teng\n¿Qué tal estás?\tspa\n")
+    .assert()
+    .success()
+    .stdout(predicate::str::starts_with(
+        "accuracy_a\t1.0000\naccuracy_b\t1.0000\ndisagreements\t0\n",
+    ))
+    .stdout(predicate::str::ends_with("improved\t0\nregressed\t0\n"));
+}
+
+#[test]
+fn test_cli_identify_progress() {
+    // The progress bar should not be written to stdout
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("input.txt");
+    std::fs::write(&input, "Hello, how are you?\n¿Qué tal estás?\n").unwrap();
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args(["identify", "--model-dir", LANGUAGEMODELS])
+        .args(["--relevant-langs", "eng,spa", "--progress"])
+        .arg(&input)
+        .assert()
+        .success()
+        .stdout("eng\nspa\n");
+}
+
+#[test]
+fn test_cli_identify_zst_input() {
+    // Compressed input should be detected from the file extension
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("input.txt.zst");
+    let compressed = zstd::encode_all("Hello, how are you?\n¿Qué tal estás?\n".as_bytes(), 0);
+    std::fs::write(&input, compressed.unwrap()).unwrap();
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args(["identify", "--model-dir", LANGUAGEMODELS])
+        .args(["--relevant-langs", "eng,spa"])
+        .arg(&input)
+        .assert()
+        .success()
+        .stdout("eng\nspa\n");
+}
+
+#[test]
+fn test_cli_identify_compress_output() {
+    // The whole gzip stream, including the footer, should be written to the output file
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("output.txt.gz");
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args(["identify", "--model-dir", LANGUAGEMODELS])
+        .args(["--relevant-langs", "eng,spa", "--compress-output", "-o"])
+        .arg(&output)
+        .write_stdin("Hello, how are you?\n¿Qué tal estás?\n")
+        .assert()
+        .success();
+    let mut decoded = String::new();
+    flate2::read::MultiGzDecoder::new(std::fs::File::open(&output).unwrap())
+        .read_to_string(&mut decoded)
+        .unwrap();
+    assert_eq!(decoded, "eng\nspa\n");
+}
+
+#[test]
+fn test_cli_identify_filter_lang() {
+    // Only lines predicted as the requested languages should be written
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "identify",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa",
+        "--filter-lang",
+        "spa_Latn",
+        "-F",
+        "ndjson",
+    ])
+    .write_stdin("Hello, how are you?\n¿Qué tal estás?\n\n")
+    .assert()
+    .success()
+    .stdout(predicate::str::is_match(r#"^\{"index":1,"lang":"spa","score":[0-9.]+\}\n$"#).unwrap());
+}
+
+#[test]
+fn test_cli_identify_filter_variety() {
+    // Predictions are macrolanguages, filtering by one of their varieties should keep them
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "identify",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,hbsbos,hbshrv,hbssrp",
+        "--filter-lang",
+        "bos",
+        "--pass-through",
+    ])
+    .write_stdin("Hello, how are you?\nDobar dan, kako ste danas?\n")
+    .assert()
+    .success()
+    .stdout("hbs\tDobar dan, kako ste danas?\n");
+}
+
+#[test]
+fn test_cli_identify_pass_through() {
+    // Each line should have the prediction, the score and the original text
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "identify",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa",
+        "--pass-through",
+        "--print-scores",
+        "-j",
+        "2",
+    ])
+    .write_stdin("Hello, how are you?\n¿Qué tal estás?\n")
+    .assert()
+    .success()
+    .stdout(
+        predicate::str::is_match(
+            "^eng\t[0-9.]+\tHello, how are you\\?\nspa\t[0-9.]+\t¿Qué tal estás\\?\n$",
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_cli_identify_skip_short() {
+    // Blank and short lines should not produce any output
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "identify",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa",
+        "--skip-empty",
+        "--min-length",
+        "3",
+    ])
+    .write_stdin("Hello, how are you?\n  \nHi\n¿Qué tal estás?\n")
+    .assert()
+    .success()
+    .stdout("eng\nspa\n");
+}
+
+#[test]
+fn test_cli_identify_skip_parallel() {
+    // Skipped lines are dropped from the batch but the rest keep their index
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "identify",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa",
+        "--skip-empty",
+        "-j",
+        "2",
+        "-F",
+        "ndjson",
+    ])
+    .write_stdin("\nHello, how are you?\n\n¿Qué tal estás?\n")
+    .assert()
+    .success()
+    .stdout(
+        predicate::str::is_match(
+            r#"^\{"index":1,"lang":"eng",[^\n]*\n\{"index":3,"lang":"spa",[^\n]*\n$"#,
+        )
+        .unwrap(),
+    );
+}
+
+#[test]
+fn test_cli_identify_max_length() {
+    // Only the beginning of the line should be identified
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "identify",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa",
+        "--max-length",
+        "20",
+    ])
+    .write_stdin("Hello my friends. ¿Qué tal estás? Muy bien gracias por preguntar\n")
+    .assert()
+    .success()
+    .stdout("eng\n")
+    .stderr(
+        predicate::str::contains("Lines truncated to 20 characters: 1")
+            .and(predicate::str::contains("Line 1 truncated").not()),
+    );
+}
+
+#[test]
+fn test_cli_identify_multiple_inputs() {
+    // Files should be processed in order, each line with its file name
+    let dir = tempfile::tempdir().unwrap();
+    let (first, second) = (dir.path().join("first.txt"), dir.path().join("second.txt"));
+    std::fs::write(&first, "Hello, how are you?\n").unwrap();
+    std::fs::write(&second, "¿Qué tal estás?\n").unwrap();
+    let manifest = dir.path().join("manifest.txt");
+    std::fs::write(&manifest, format!("{}\n", second.display())).unwrap();
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args(["identify", "--model-dir", LANGUAGEMODELS])
+        .args(["--relevant-langs", "eng,spa", "--include-filename"])
+        .arg(&first)
+        .arg("--manifest")
+        .arg(&manifest)
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}\teng\n{}\tspa\n",
+            first.display(),
+            second.display()
+        ));
+}
+
+#[test]
+fn test_cli_identify_positional_inputs() {
+    // Two positional files are both inputs, the second one must not be overwritten
+    let dir = tempfile::tempdir().unwrap();
+    let (first, second) = (dir.path().join("a.txt"), dir.path().join("b.txt"));
+    std::fs::write(&first, "Hello, how are you?\n").unwrap();
+    std::fs::write(&second, "¿Qué tal estás?\n").unwrap();
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args(["identify", "--model-dir", LANGUAGEMODELS])
+        .args(["--relevant-langs", "eng,spa"])
+        .arg(&first)
+        .arg(&second)
+        .assert()
+        .success()
+        .stdout("eng\nspa\n");
+    assert_eq!(
+        std::fs::read_to_string(&second).unwrap(),
+        "¿Qué tal estás?\n"
+    );
+}
+
+#[test]
+fn test_cli_identify_ndjson_multiple_inputs() {
+    // Indexes should not restart with each input file
+    let dir = tempfile::tempdir().unwrap();
+    let (first, second) = (dir.path().join("first.txt"), dir.path().join("second.txt"));
+    std::fs::write(&first, "Hello, how are you?\n").unwrap();
+    std::fs::write(&second, "¿Qué tal estás?\n").unwrap();
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args(["identify", "--model-dir", LANGUAGEMODELS])
+        .args(["--relevant-langs", "eng,spa", "-F", "ndjson", "-o", "-"])
+        .arg(&first)
+        .arg(&second)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(
+                r#"^\{"index":0,"lang":"eng",[^\n]*\n\{"index":1,"lang":"spa","#,
+            )
+            .unwrap(),
+        );
+}
+
+#[test]
+fn test_cli_identify_input_column() {
+    // Only the column should be identified and the prediction appended after all the columns
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args([
+        "identify",
+        "--model-dir",
+        LANGUAGEMODELS,
+        "--relevant-langs",
+        "eng,spa",
+        "--input-column",
+        "1",
+        "--pass-through",
+    ])
+    .write_stdin("1\tHello, how are you?\tx\n2\t¿Qué tal estás?\ty\n")
+    .assert()
+    .success()
+    .stdout("1\tHello, how are you?\tx\teng\n2\t¿Qué tal estás?\ty\tspa\n");
+}
+
+#[test]
+fn test_cli_create_model_languagelist() {
+    // Languagelist should contain only the valid languages trained
+    let dir = tempfile::tempdir().unwrap();
+    let train = dir.path().join("train");
+    let output = dir.path().join("output");
+    std::fs::create_dir_all(&train).unwrap();
+    std::fs::create_dir_all(&output).unwrap();
+    std::fs::write(train.join("cat.train"), "L'aigua clara\nBon dia\n").unwrap();
+    std::fs::write(train.join("eng.train"), "Clear water\nGood morning\n").unwrap();
+    std::fs::write(output.join("xyz.word.model"), "0\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args(["create-model", "--generate-languagelist"])
+        .arg(&output)
+        .arg(train.join("cat.train"))
+        .arg(train.join("eng.train"))
+        .assert()
+        .success();
+    let languagelist = std::fs::read_to_string(output.join("languagelist")).unwrap();
+    assert_eq!(languagelist, "cat\neng\n");
+}
+
+#[test]
+fn test_cli_create_model_min_frequency() {
+    // Rare n-grams should be removed and not counted in the total
+    let dir = tempfile::tempdir().unwrap();
+    let train = dir.path().join("cat.train");
+    std::fs::write(&train, "aigua aigua clara\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args(["create-model", "--min-frequency", "2"])
+        .arg(dir.path())
+        .arg(&train)
+        .assert()
+        .success();
+    let model = std::fs::read_to_string(dir.path().join("cat.word.model")).unwrap();
+    assert_eq!(model, "2\naigua\t2\n");
+}
+
+#[test]
+fn test_cli_create_model_incremental() {
+    // Up to date model files should only be generated again with --force
+    let dir = tempfile::tempdir().unwrap();
+    let train = dir.path().join("cat.train");
+    let model = dir.path().join("cat.word.model");
+    std::fs::write(&train, "aigua clara\n").unwrap();
+    std::fs::write(&model, "0\n").unwrap();
+    // Make sure the training file is older than the model file
+    let past = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+    std::fs::File::options()
+        .write(true)
+        .open(&train)
+        .unwrap()
+        .set_modified(past)
+        .unwrap();
+
+    let create_model = |args: &[&str]| {
+        Command::cargo_bin("heliport")
+            .unwrap()
+            .arg("create-model")
+            .args(args)
+            .arg(dir.path())
+            .arg(&train)
+            .assert()
+            .success();
+    };
+    create_model(&["--incremental"]);
+    assert_eq!(std::fs::read_to_string(&model).unwrap(), "0\n");
+    assert!(dir.path().join("cat.bigram.model").exists());
+    create_model(&["--incremental", "--force"]);
+    assert_eq!(
+        std::fs::read_to_string(&model).unwrap(),
+        "2\naigua\t1\nclara\t1\n"
+    );
+}
+
+#[test]
+fn test_cli_validate_corpus() {
+    // Each file should be reported and fail if any of them is not valid
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("cat.train"), "L'aigua clara\n").unwrap();
+    std::fs::write(dir.path().join("rus.train"), "Clear water\n").unwrap();
+    std::fs::write(dir.path().join("spa.train"), b"Agua \xff\n").unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    cmd.args(["validate-corpus", "--min-lines", "1"])
+        .arg(dir.path())
+        .assert()
+        .failure()
+        .stdout(format!(
+            "{0}/cat.train\tok\n{0}/rus.train\twarning\n{0}/spa.train\terror\n",
+            dir.path().display()
+        ))
+        .stderr(predicate::str::contains("Invalid UTF-8 in line 1"));
+}
+
+#[test]
+fn test_cli_cross_validate() {
+    // Should report the accuracy of each fold and the average
+    let dir = tempfile::tempdir().unwrap();
+    let sentences = [
+        ("cat", "L'aigua clara del riu baixa per la muntanya\nBon dia, com estàs avui?\nEls nens juguen a la plaça cada tarda\nLa meva germana treballa en una escola\n"),
+        ("eng", "The clear water of the river flows down the mountain\nGood morning, how are you today?\nThe children play in the square every afternoon\nMy sister works in a school\n"),
+    ];
+    for (lang, text) in sentences {
+        std::fs::write(dir.path().join(format!("{lang}.train")), text).unwrap();
+    }
+
+    let mut cmd = Command::cargo_bin("heliport").unwrap();
+    let output = cmd
+        .args(["cross-validate", "--folds", "2", "--input-dir"])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines[0], "fold\taccuracy\tmacro-f1\tmicro-f1\tsupport");
+    assert!(lines[1].starts_with("1\t") && lines[1].ends_with("\t4"));
+    assert!(lines[2].starts_with("2\t") && lines[2].ends_with("\t4"));
+    assert!(lines[3].starts_with("average\t"));
+    // Followed by the metrics of all the folds together
+    assert_eq!(lines[5], "lang\tprecision\trecall\tf1\tsupport");
+    assert!(lines.iter().any(|l| l.starts_with("micro-f1\t")));
+}
+
+#[cfg(feature = "grpc")]
+#[test]
+fn test_cli_serve_grpc() {
+    use heliport_grpc::language_identifier_client::LanguageIdentifierClient;
+    use heliport_grpc::{IdentifyBatchRequest, IdentifyRequest};
+    use std::time::Duration;
+
+    // Start the server in the background and query it with the client
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut server = std::process::Command::new(assert_cmd::cargo::cargo_bin("heliport"))
+        .args([
+            "-q",
+            "serve",
+            "--model-dir",
+            LANGUAGEMODELS,
+            "--relevant-langs",
+            "eng,spa",
+        ])
+        .args(["--grpc-port", &port.to_string()])
+        .spawn()
+        .unwrap();
+
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let result = runtime.block_on(async {
+        // Wait for the server to be ready
+        let mut client = None;
+        for _ in 0..300 {
+            match LanguageIdentifierClient::connect(format!("http://127.0.0.1:{port}")).await {
+                Ok(c) => {
+                    client = Some(c);
+                    break;
+                }
+                Err(_) => std::thread::sleep(Duration::from_millis(100)),
+            }
+        }
+        let mut client = client.expect("Could not connect to the server");
+        let single = client
+            .identify(IdentifyRequest {
+                text: String::from("Hello, how are you?"),
+                topk: 0,
+                ignore_confidence: false,
+            })
+            .await?
+            .into_inner();
+        let batch = client
+            .identify_batch(IdentifyBatchRequest {
+                texts: vec![
+                    String::from("Hello, how are you?"),
+                    String::from("¿Qué tal estás?"),
+                ],
+                topk: 2,
+                ignore_confidence: false,
+            })
+            .await?
+            .into_inner();
+        Ok::<_, tonic::Status>((single, batch))
+    });
+    server.kill().unwrap();
+    server.wait().unwrap();
+
+    let (single, batch) = result.unwrap();
+    assert_eq!(single.predictions.len(), 1);
+    assert_eq!(single.predictions[0].lang, "eng");
+    // Batch returns the top 2 of each text in the input order
+    let best: Vec<&str> = batch
+        .responses
+        .iter()
+        .map(|r| {
+            assert_eq!(r.predictions.len(), 2);
+            r.predictions[0].lang.as_str()
+        })
+        .collect();
+    assert_eq!(best, vec!["eng", "spa"]);
+}
+
+#[cfg(feature = "serve")]
+#[test]
+fn test_cli_serve_http() {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::time::Duration;
+
+    // Send a request and return the body of the response
+    fn request(port: u16, method: &str, path: &str, body: &str) -> std::io::Result<String> {
+        let mut stream = TcpStream::connect(("127.0.0.1", port))?;
+        write!(
+            stream,
+            "{method} {path} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+        Ok(response.split("\r\n\r\n").nth(1).unwrap_or("").to_string())
+    }
+
+    // Start the server in the background
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut server = std::process::Command::new(assert_cmd::cargo::cargo_bin("heliport"))
+        .args([
+            "-q",
+            "serve",
+            "--model-dir",
+            LANGUAGEMODELS,
+            "--relevant-langs",
+            "eng,spa",
+        ])
+        .args(["--port", &port.to_string()])
+        .spawn()
+        .unwrap();
+
+    // Wait for the server to be ready
+    let mut languages = None;
+    for _ in 0..300 {
+        match request(port, "GET", "/languages", "") {
+            Ok(body) => {
+                languages = Some(body);
+                break;
+            }
+            Err(_) => std::thread::sleep(Duration::from_millis(100)),
+        }
+    }
+    let single = request(
+        port,
+        "POST",
+        "/identify",
+        r#"{"text":"Hello, how are you?"}"#,
+    );
+    let batch = request(
+        port,
+        "POST",
+        "/identify/batch",
+        r#"{"texts":["Hello, how are you?","¿Qué tal estás?"]}"#,
+    );
+    let openapi = request(port, "GET", "/openapi.json", "");
+    server.kill().unwrap();
+    server.wait().unwrap();
+
+    let languages: serde_json::Value = serde_json::from_str(&languages.unwrap()).unwrap();
+    let codes: Vec<_> = languages
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| l["code"].as_str().unwrap())
+        .collect();
+    assert_eq!(codes, vec!["eng", "spa"]);
+    let single: serde_json::Value = serde_json::from_str(&single.unwrap()).unwrap();
+    assert_eq!(single["lang"], "eng");
+    let batch: serde_json::Value = serde_json::from_str(&batch.unwrap()).unwrap();
+    assert_eq!(batch["predictions"][0]["lang"], "eng");
+    assert_eq!(batch["predictions"][1]["lang"], "spa");
+    let openapi: serde_json::Value = serde_json::from_str(&openapi.unwrap()).unwrap();
+    assert!(openapi["paths"]["/identify/batch"].is_object());
+}